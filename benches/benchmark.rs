@@ -16,5 +16,43 @@ fn criterion_benchmark(c: &mut Bencher) {
     });
 }
 
-benchmark_group!(benches, criterion_benchmark);
+/// Certificates make up most of large bundles, such as a system's trusted roots.
+///
+/// Run with `--features simd` to compare the SIMD base64 decoder.
+fn large_bundle(c: &mut Bencher) {
+    let data = include_bytes!("../tests/data/certificate.chain.pem").repeat(100);
+    c.iter(|| {
+        let mut reader = BufReader::new(&data[..]);
+        assert_eq!(
+            rustls_pemfile::certs(&mut reader)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+                .len(),
+            300
+        );
+    });
+}
+
+/// As for `large_bundle`, but without dynamic dispatch to the reader.
+fn large_bundle_generic(c: &mut Bencher) {
+    let data = include_bytes!("../tests/data/certificate.chain.pem").repeat(100);
+    c.iter(|| {
+        let reader = BufReader::new(&data[..]);
+        assert_eq!(
+            rustls_pemfile::PemReader::new(reader)
+                .certs()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+                .len(),
+            300
+        );
+    });
+}
+
+benchmark_group!(
+    benches,
+    criterion_benchmark,
+    large_bundle,
+    large_bundle_generic
+);
 benchmark_main!(benches);
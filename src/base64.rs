@@ -0,0 +1,849 @@
+//! Constant-time base64 encoding and decoding.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::writer::LineEnding;
+
+/// Decode base64 `input`, writing the result into `output`.
+///
+/// `input` is treated as secret, as described for [`Decoder`].
+///
+/// Returns the prefix of `output` that was written to; or the error, with the offset in
+/// `input` at which it was found.
+#[cfg(test)]
+#[cfg(feature = "std")]
+pub(crate) fn decode_secret<'a>(
+    input: &[u8],
+    output: &'a mut [u8],
+) -> Result<&'a [u8], (Error, usize)> {
+    decode(input, output, Decoder::new(true))
+}
+
+/// Decode base64 `input`, writing the result into `output`.
+///
+/// `input` is treated as public information, so its value may
+/// be leaked via side channels.
+///
+/// Returns the prefix of `output` that was written to; or the error, with the offset in
+/// `input` at which it was found.
+pub(crate) fn decode_public<'a>(
+    input: &[u8],
+    output: &'a mut [u8],
+) -> Result<&'a [u8], (Error, usize)> {
+    decode(input, output, Decoder::new(false))
+}
+
+/// Provide an upper limit on how much space could be required
+/// to decode a base64 encoding of len `base64_len`.
+pub(crate) const fn decoded_length(base64_len: usize) -> usize {
+    base64_len.div_ceil(4) * 3
+}
+
+/// Encode `input` as padded base64, appending the result to `output`.
+///
+/// `input` is treated as secret, as described for [`Encoder`].
+pub(crate) fn encode(input: &[u8], output: &mut String) {
+    output.reserve(encoded_length(input.len()));
+
+    let mut encoder = Encoder::new();
+    encoder.update(input, output);
+    encoder.finish(output);
+}
+
+/// An incremental encoder, producing padded base64.
+///
+/// Input is supplied in pieces of any size with [`Encoder::update()`], and the
+/// encoding is completed with [`Encoder::finish()`]; so large payloads can be
+/// encoded without holding all of the input (or its encoding) at once.
+///
+/// Input is treated as secret, so the mapping of its value to base64
+/// characters is done without branches or memory accesses that depend on
+/// it.  The length of the input is not secret.
+///
+/// ```
+/// use rustls_pemfile::base64::Encoder;
+/// use rustls_pemfile::LineEnding;
+///
+/// let mut out = String::new();
+/// let mut encoder = Encoder::new().wrap(4, LineEnding::Lf);
+/// encoder.update(b"hel", &mut out);
+/// encoder.update(b"lo", &mut out);
+/// encoder.finish(&mut out);
+/// assert_eq!(out, "aGVs\nbG8=\n");
+/// ```
+#[derive(Clone)]
+pub struct Encoder {
+    pending: [u8; 3],
+    pending_len: usize,
+    wrap: Option<(usize, LineEnding)>,
+    column: usize,
+}
+
+impl Encoder {
+    /// Make a new `Encoder`, which writes all the output on one line.
+    pub fn new() -> Self {
+        Self {
+            pending: [0; 3],
+            pending_len: 0,
+            wrap: None,
+            column: 0,
+        }
+    }
+
+    /// Wrap the output into lines of `width` characters, each terminated by `ending`.
+    ///
+    /// The final line is terminated too, unless there was no output at all.  Values of
+    /// `width` less than 4 are treated as 4.
+    pub fn wrap(mut self, width: usize, ending: LineEnding) -> Self {
+        self.wrap = Some((width.max(4), ending));
+        self
+    }
+
+    /// Encode `input`, appending the result to `output`.
+    ///
+    /// Up to two bytes of `input` may be held back until the next call to `update()`
+    /// or `finish()`.
+    pub fn update(&mut self, mut input: &[u8], output: &mut String) {
+        if self.pending_len > 0 {
+            let take = (3 - self.pending_len).min(input.len());
+            let (head, rest) = input.split_at(take);
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(head);
+            self.pending_len += take;
+            input = rest;
+
+            if self.pending_len < 3 {
+                return;
+            }
+
+            self.push_block(self.pending, 3, output);
+            self.pending_len = 0;
+        }
+
+        let mut blocks = input.chunks_exact(3);
+        for block in &mut blocks {
+            self.push_block([block[0], block[1], block[2]], 3, output);
+        }
+
+        let rest = blocks.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    /// Encode any remaining input, with padding, appending the result to `output`.
+    pub fn finish(mut self, output: &mut String) {
+        if self.pending_len > 0 {
+            let mut block = [0u8; 3];
+            block[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+            self.push_block(block, self.pending_len, output);
+        }
+
+        if let Some((_, ending)) = self.wrap {
+            if self.column > 0 {
+                output.push_str(ending.as_str());
+            }
+        }
+    }
+
+    /// Append the encoding of the first `len` bytes of `block`.
+    fn push_block(&mut self, block: [u8; 3], len: usize, output: &mut String) {
+        let [b0, b1, b2] = block;
+        let sextets = [
+            b0 >> 2,
+            ((b0 & 0x03) << 4) | (b1 >> 4),
+            ((b1 & 0x0f) << 2) | (b2 >> 6),
+            b2 & 0x3f,
+        ];
+
+        for (i, sextet) in sextets.iter().copied().enumerate() {
+            self.push_char(
+                match i <= len {
+                    true => char::from(encode_sextet(sextet)),
+                    false => '=',
+                },
+                output,
+            );
+        }
+    }
+
+    fn push_char(&mut self, ch: char, output: &mut String) {
+        if let Some((width, ending)) = self.wrap {
+            if self.column == width {
+                output.push_str(ending.as_str());
+                self.column = 0;
+            }
+        }
+
+        output.push(ch);
+        self.column += 1;
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // pending input is secret
+        f.debug_struct("Encoder")
+            .field("wrap", &self.wrap)
+            .field("column", &self.column)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The length of the padded base64 encoding of `len` bytes.
+pub(crate) const fn encoded_length(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+fn decode<'a>(
+    input: &[u8],
+    output: &'a mut [u8],
+    mut decoder: Decoder,
+) -> Result<&'a [u8], (Error, usize)> {
+    let mut decoded = Vec::with_capacity(decoded_length(input.len()));
+    decoder.update(input, &mut decoded)?;
+
+    // errors in the final block are found at the end of the input
+    let end = input.len();
+    decoder.finish(&mut decoded).map_err(|error| (error, end))?;
+    let chunk = output
+        .get_mut(..decoded.len())
+        .ok_or((Error::InsufficientOutputSpace, end))?;
+    chunk.copy_from_slice(&decoded);
+    Ok(chunk)
+}
+
+/// An incremental decoder, for base64 with or without padding.
+///
+/// Input is supplied in pieces of any size with [`Decoder::update()`], and the decoding
+/// is completed with [`Decoder::finish()`]; so a large encoding can be decoded as it is
+/// read, without holding all of it at once.  Whitespace is skipped.
+///
+/// Input is treated as secret, unless the decoder is made for public input; in which case,
+/// with the "simd" feature, it is decoded with SIMD instructions where possible.  For
+/// secret input, efforts are made to avoid leaking its value via side channels, such as
+/// timing, memory accesses, and execution trace.
+///
+/// The following is deemed non-secret information:
+///
+/// - Appearance of whitespace in the input
+/// - Erroneous characters in the input (indeed, the first illegal
+///   character is quoted in the error type)
+/// - The length of the input
+/// - The length of the output
+pub(crate) struct Decoder {
+    secret: bool,
+    buffer: u64,
+    used: usize,
+    shift: i32,
+    pad_mask: u8,
+}
+
+impl Decoder {
+    /// Make a new `Decoder`, for `secret` input or not.
+    pub(crate) fn new(secret: bool) -> Self {
+        Self {
+            secret,
+            buffer: 0,
+            used: 0,
+            shift: SHIFT_INITIAL,
+            pad_mask: 0,
+        }
+    }
+
+    /// Decode `input`, appending the result to `output`.
+    ///
+    /// Up to seven characters of `input` may be held back until the next call to
+    /// `update()` or `finish()`.  Returns any error with the offset in `input` at which it
+    /// was found.
+    pub(crate) fn update(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), (Error, usize)> {
+        #[cfg(feature = "simd")]
+        if !self.secret && self.update_simd(input, output) {
+            return Ok(());
+        }
+
+        match self.secret {
+            true => self.update_with(input, output, CodePoint::decode_secret),
+            false => self.update_with(input, output, CodePoint::decode_public),
+        }
+    }
+
+    fn update_with(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        decode_byte: impl Fn(u8) -> CodePoint,
+    ) -> Result<(), (Error, usize)> {
+        let mut index = 0;
+        while let Some(&byte) = input.get(index) {
+            // between quads, four code points are taken at once, unless the
+            // quad contains whitespace, padding or anything invalid.
+            if self.used.is_multiple_of(4) && self.pad_mask == 0 {
+                if let Some(&[b0, b1, b2, b3]) = input.get(index..index + 4) {
+                    let quad = [b0, b1, b2, b3].map(|b| decode_byte(b).0);
+                    if quad.iter().fold(0, |acc, n| acc | n) < 64 {
+                        let bits = quad.iter().fold(0, |acc, &n| acc << 6 | n as u64);
+                        self.buffer |= bits << (self.shift - 18);
+                        self.shift -= 24;
+                        self.used += 4;
+                        index += 4;
+                        self.flush(output);
+                        continue;
+                    }
+                }
+            }
+
+            let (item, pad) = match decode_byte(byte) {
+                CodePoint::WHITESPACE => {
+                    index += 1;
+                    continue;
+                }
+                CodePoint::INVALID => return Err((Error::InvalidCharacter(byte), index)),
+                CodePoint::PAD => (0, 1),
+                CodePoint(n) => (n, 0),
+            };
+
+            // a full block is only kept if it is padded, so must be the last
+            if self.used == 8 {
+                return Err((Error::PrematurePadding, index));
+            }
+
+            self.buffer |= (item as u64) << self.shift;
+            self.shift -= 6;
+            self.pad_mask |= pad << self.used;
+            self.used += 1;
+            index += 1;
+            self.flush(output);
+        }
+
+        Ok(())
+    }
+
+    /// Write out the buffered block, if it is full and unpadded.
+    ///
+    /// We collect 8 code points (therefore: 6 output bytes) into `buffer`.  This keeps
+    /// the decoding loop as tight as possible.
+    fn flush(&mut self, output: &mut Vec<u8>) {
+        if self.used == 8 && self.pad_mask == 0 {
+            output.extend_from_slice(&self.buffer.to_be_bytes()[2..]);
+            self.buffer = 0;
+            self.used = 0;
+            self.shift = SHIFT_INITIAL;
+        }
+    }
+
+    /// Whether the input is treated as secret.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    /// Whether the input so far is a whole number of quads, without padding; so that
+    /// decoding could continue with a new `Decoder`.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn between_quads(&self) -> bool {
+        self.used.is_multiple_of(4) && self.pad_mask == 0
+    }
+
+    /// Decode `input` with SIMD instructions, returning whether that was possible.
+    ///
+    /// It is only possible between blocks, and for whole quads of base64 characters with
+    /// no padding or whitespace other than a final line ending: such as the lines of PEM.
+    /// Other input, including any that is invalid, is left to the scalar decoder.
+    ///
+    /// The output is written into the spare capacity of `output`, so is not zeroed first;
+    /// and `output` is unchanged unless this succeeds.
+    #[cfg(feature = "simd")]
+    fn update_simd(&mut self, input: &[u8], output: &mut Vec<u8>) -> bool {
+        let end = input
+            .iter()
+            .rposition(|&b| !matches!(b, b'\n' | b'\r'))
+            .map_or(0, |i| i + 1);
+        let line = &input[..end];
+        if self.used != 0 || !line.len().is_multiple_of(4) || line.contains(&b'=') {
+            return false;
+        }
+
+        base64_simd::STANDARD.decode_append(line, output).is_ok()
+    }
+
+    /// Decode the final block, appending the result to `output`.
+    ///
+    /// Returns whether the whole input was canonical: padded, with any unused bits zero.
+    pub(crate) fn finish(self, output: &mut Vec<u8>) -> Result<bool, Error> {
+        let Self {
+            mut buffer,
+            mut used,
+            mut pad_mask,
+            ..
+        } = self;
+
+        // reduce to final block
+        if used > 4 {
+            if pad_mask & 0b0000_1111 != 0 {
+                return Err(Error::PrematurePadding);
+            }
+            output.extend_from_slice(&buffer.to_be_bytes()[2..5]);
+            buffer <<= 24;
+            pad_mask >>= 4;
+            used -= 4;
+        }
+
+        // the number of trailing bytes, and the bits of `buffer` left unused
+        let (len, unused) = match (used, pad_mask) {
+            // no trailing bytes
+            (0, 0b0000) => (0, 0),
+            // 4 trailing code points, no padding
+            (4, 0b0000) => (3, 0),
+            // 4 trailing code points with one padding char, or 3 trailing code points
+            (4, 0b1000) | (3, 0b0000) => (2, 0b11 << 30),
+            // 4 trailing code points with two padding chars, or 2 trailing code points
+            (4, 0b1100) | (2, 0b0000) => (1, 0b1111 << 36),
+            // everything else is illegal
+            _ => return Err(Error::InvalidTrailingPadding),
+        };
+
+        output.extend_from_slice(&buffer.to_be_bytes()[2..2 + len]);
+        Ok(matches!(used, 0 | 4) && buffer & unused == 0)
+    }
+}
+
+impl fmt::Debug for Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // pending input may be secret
+        f.debug_struct("Decoder")
+            .field("secret", &self.secret)
+            .field("used", &self.used)
+            .finish_non_exhaustive()
+    }
+}
+
+const SHIFT_INITIAL: i32 = (8 - 1) * 6;
+
+/// An error decoding base64.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Given character is not valid in base64 alphabet.
+    InvalidCharacter(u8),
+
+    /// A padding character (`=`) appeared outside the final
+    /// block of 4 characters.
+    PrematurePadding,
+
+    /// The padding characters at the end of the input were invalid.
+    InvalidTrailingPadding,
+
+    /// Not enough space in output buffer.
+    ///
+    /// The output is sized from the length of the input, so this is not expected.
+    InsufficientOutputSpace,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCharacter(byte) => write!(f, "invalid character {:#04x}", byte),
+            Self::PrematurePadding => f.write_str("padding before the final block"),
+            Self::InvalidTrailingPadding => f.write_str("invalid trailing padding"),
+            Self::InsufficientOutputSpace => f.write_str("insufficient output space"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct CodePoint(u8);
+
+impl CodePoint {
+    const WHITESPACE: Self = Self(0xf0);
+    const PAD: Self = Self(0xf1);
+    const INVALID: Self = Self(0xf2);
+}
+
+impl CodePoint {
+    /// Side-channel rules:
+    ///
+    /// - code paths that produce `CodePoint(n)` must not make
+    ///   `n` observable via a side channel.
+    /// - other code paths -- whitespace, padding or invalid -- need not,
+    ///   these are not considered secret conditions.
+    fn decode_secret(b: u8) -> Self {
+        let is_upper = u8_in_range(b, b'A', b'Z');
+        let is_lower = u8_in_range(b, b'a', b'z');
+        let is_digit = u8_in_range(b, b'0', b'9');
+        let is_plus = u8_equals(b, b'+');
+        let is_slash = u8_equals(b, b'/');
+        let is_pad = u8_equals(b, b'=');
+        let is_space = u8_in_range(b, b'\t', b'\r') | u8_equals(b, b' ');
+
+        let is_invalid = !(is_lower | is_upper | is_digit | is_plus | is_slash | is_pad | is_space);
+
+        Self(
+            (is_upper & b.wrapping_sub(b'A'))
+                | (is_lower & (b.wrapping_sub(b'a').wrapping_add(26)))
+                | (is_digit & (b.wrapping_sub(b'0').wrapping_add(52)))
+                | (is_plus & 62)
+                | (is_slash & 63)
+                | (is_space & Self::WHITESPACE.0)
+                | (is_pad & Self::PAD.0)
+                | (is_invalid & Self::INVALID.0),
+        )
+    }
+
+    fn decode_public(a: u8) -> Self {
+        const TABLE: [CodePoint; 256] = [
+            // 0x00..0x0f
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::WHITESPACE,
+            CodePoint::WHITESPACE,
+            CodePoint::WHITESPACE,
+            CodePoint::WHITESPACE,
+            CodePoint::WHITESPACE,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0x10..0x1f
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0x20..0x2f
+            CodePoint::WHITESPACE,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint(62),
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint(63),
+            // 0x30..0x3f
+            CodePoint(52),
+            CodePoint(53),
+            CodePoint(54),
+            CodePoint(55),
+            CodePoint(56),
+            CodePoint(57),
+            CodePoint(58),
+            CodePoint(59),
+            CodePoint(60),
+            CodePoint(61),
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::PAD,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0x40..0x4f
+            CodePoint::INVALID,
+            CodePoint(0),
+            CodePoint(1),
+            CodePoint(2),
+            CodePoint(3),
+            CodePoint(4),
+            CodePoint(5),
+            CodePoint(6),
+            CodePoint(7),
+            CodePoint(8),
+            CodePoint(9),
+            CodePoint(10),
+            CodePoint(11),
+            CodePoint(12),
+            CodePoint(13),
+            CodePoint(14),
+            // 0x50..0x5f
+            CodePoint(15),
+            CodePoint(16),
+            CodePoint(17),
+            CodePoint(18),
+            CodePoint(19),
+            CodePoint(20),
+            CodePoint(21),
+            CodePoint(22),
+            CodePoint(23),
+            CodePoint(24),
+            CodePoint(25),
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0x60..0x6f
+            CodePoint::INVALID,
+            CodePoint(26),
+            CodePoint(27),
+            CodePoint(28),
+            CodePoint(29),
+            CodePoint(30),
+            CodePoint(31),
+            CodePoint(32),
+            CodePoint(33),
+            CodePoint(34),
+            CodePoint(35),
+            CodePoint(36),
+            CodePoint(37),
+            CodePoint(38),
+            CodePoint(39),
+            CodePoint(40),
+            // 0x70..0x7f
+            CodePoint(41),
+            CodePoint(42),
+            CodePoint(43),
+            CodePoint(44),
+            CodePoint(45),
+            CodePoint(46),
+            CodePoint(47),
+            CodePoint(48),
+            CodePoint(49),
+            CodePoint(50),
+            CodePoint(51),
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0x80..0x8f
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0x90..0x9f
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0xa0..0xaf
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0xb0..0xbf
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0xc0..0xcf
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0xd0..0xdf
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0xe0..0xef
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            // 0xf0..0xff
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+            CodePoint::INVALID,
+        ];
+
+        TABLE[a as usize]
+    }
+}
+
+/// Maps `n` (which must be less than 64) to its base64 character.
+///
+/// Side-channel rules as for `CodePoint::decode_secret`: the value of `n` must
+/// not be observable.
+fn encode_sextet(n: u8) -> u8 {
+    let is_upper = u8_less_than(n, 26);
+    let is_lower = !is_upper & u8_less_than(n, 52);
+    let is_digit = !(is_upper | is_lower) & u8_less_than(n, 62);
+    let is_plus = u8_equals(n, 62);
+    let is_slash = u8_equals(n, 63);
+
+    (is_upper & n.wrapping_add(b'A'))
+        | (is_lower & n.wrapping_sub(26).wrapping_add(b'a'))
+        | (is_digit & n.wrapping_sub(52).wrapping_add(b'0'))
+        | (is_plus & b'+')
+        | (is_slash & b'/')
+}
+
+/// Returns 0xff if `a` in `lo..=hi`.
+///
+/// lo..=hi must not be 0..=255.  Callers in this file have constant
+/// `lo` and `hi`, and this function is private to this file.
+fn u8_in_range(a: u8, lo: u8, hi: u8) -> u8 {
+    debug_assert!(lo <= hi);
+    debug_assert!(hi - lo != 255);
+    let a = a.wrapping_sub(lo);
+    u8_less_than(a, (hi - lo).wrapping_add(1))
+}
+
+/// Returns 0xff if a < b, 0 otherwise.
+fn u8_less_than(a: u8, b: u8) -> u8 {
+    let a = u16::from(a);
+    let b = u16::from(b);
+    u8_broadcast16(a.wrapping_sub(b))
+}
+
+/// Returns 0xff if a == b, 0 otherwise.
+fn u8_equals(a: u8, b: u8) -> u8 {
+    let diff = a ^ b;
+    u8_nonzero(diff)
+}
+
+/// Returns 0xff if a != 0, 0 otherwise.
+fn u8_nonzero(x: u8) -> u8 {
+    u8_broadcast8(!x & x.wrapping_sub(1))
+}
+
+/// Broadcasts the top bit of `x`
+///
+/// In other words, if the top bit of `x` is set,
+/// returns 0xff else 0x00.
+fn u8_broadcast8(x: u8) -> u8 {
+    let msb = x >> 7;
+    0u8.wrapping_sub(msb)
+}
+
+/// Broadcasts the top bit of `x`
+///
+/// In other words, if the top bit of `x` is set,
+/// returns 0xff else 0x00.
+fn u8_broadcast16(x: u16) -> u8 {
+    let msb = x >> 15;
+    0u8.wrapping_sub(msb as u8)
+}
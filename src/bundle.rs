@@ -0,0 +1,147 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+#[cfg(feature = "std")]
+use std::io;
+
+use pki_types::{
+    CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, PrivatePkcs1KeyDer,
+    PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
+};
+
+#[cfg(feature = "std")]
+use crate::pemfile::ReadError;
+use crate::pemfile::{Error, Item};
+#[cfg(feature = "std")]
+use crate::reader::PemReader;
+use crate::reader::{ParserOptions, SliceReader};
+
+/// Every item in some PEM, sorted by kind.
+///
+/// This is for applications that need several kinds of item from the same input, such as
+/// the certificates, CRLs and keys of a provisioning file, which can then be read once
+/// rather than once for each kind.  The items of each kind are in the order read, and
+/// sections with labels that are not recognised are kept in `unknown`.
+///
+/// ```
+/// use rustls_pemfile::PemBundle;
+///
+/// let bundle = PemBundle::from_slice(include_bytes!("../tests/data/zen.pem")).unwrap();
+/// assert_eq!(bundle.certs.len(), 4);
+/// assert_eq!(bundle.crls.len(), 1);
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Default, PartialEq)]
+pub struct PemBundle {
+    /// The certificates.
+    pub certs: Vec<CertificateDer<'static>>,
+    /// The certificate revocation lists.
+    pub crls: Vec<CertificateRevocationListDer<'static>>,
+    /// The certificate signing requests.
+    pub csrs: Vec<CertificateSigningRequestDer<'static>>,
+    /// The PKCS#1 ("RSA PRIVATE KEY") private keys.
+    pub pkcs1_keys: Vec<PrivatePkcs1KeyDer<'static>>,
+    /// The PKCS#8 ("PRIVATE KEY") private keys.
+    pub pkcs8_keys: Vec<PrivatePkcs8KeyDer<'static>>,
+    /// The SEC1 ("EC PRIVATE KEY") private keys.
+    pub sec1_keys: Vec<PrivateSec1KeyDer<'static>>,
+    /// The SubjectPublicKeyInfo ("PUBLIC KEY") public keys.
+    pub public_keys: Vec<SubjectPublicKeyInfoDer<'static>>,
+    /// Every other item, such as parameters, or sections with labels that are not
+    /// recognised.
+    pub unknown: Vec<Item>,
+}
+
+impl PemBundle {
+    /// Make a new, empty `PemBundle`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read every item from `rd`.
+    ///
+    /// Errors are as for [`read_all()`][crate::read_all()], but as a [`ReadError`].
+    #[cfg(feature = "std")]
+    pub fn from_reader(rd: impl io::BufRead) -> Result<Self, ReadError> {
+        PemReader::with_options(rd, ParserOptions::new().yield_unknown(true))
+            .map(|item| item.map_err(ReadError::from))
+            .collect()
+    }
+
+    /// Read every item from `input`.
+    ///
+    /// Errors are as for [`read_all_from_slice()`][crate::read_all_from_slice()].
+    pub fn from_slice(input: &[u8]) -> Result<Self, Error> {
+        SliceReader::with_options(input, ParserOptions::new().yield_unknown(true)).collect()
+    }
+
+    /// Remove certificates that are byte-for-byte identical to an earlier one, returning how
+    /// many were removed.
+    ///
+    /// See [`dedup_certs()`][crate::dedup_certs()].
+    pub fn dedup_certs(&mut self) -> usize {
+        dedup_certs(&mut self.certs)
+    }
+
+    /// Add `item` to those of its kind.
+    pub fn push(&mut self, item: Item) {
+        match item {
+            Item::X509Certificate(cert) => self.certs.push(cert),
+            Item::Crl(crl) => self.crls.push(crl),
+            Item::Csr(csr) => self.csrs.push(csr),
+            Item::Pkcs1Key(key) => self.pkcs1_keys.push(key),
+            Item::Pkcs8Key(key) => self.pkcs8_keys.push(key),
+            Item::Sec1Key(key) => self.sec1_keys.push(key),
+            Item::SubjectPublicKeyInfo(key) => self.public_keys.push(key),
+            item => self.unknown.push(item),
+        }
+    }
+}
+
+impl Extend<Item> for PemBundle {
+    fn extend<I: IntoIterator<Item = Item>>(&mut self, items: I) {
+        items.into_iter().for_each(|item| self.push(item));
+    }
+}
+
+impl FromIterator<Item> for PemBundle {
+    fn from_iter<I: IntoIterator<Item = Item>>(items: I) -> Self {
+        let mut bundle = Self::new();
+        bundle.extend(items);
+        bundle
+    }
+}
+
+/// Remove certificates that are byte-for-byte identical to an earlier one in `certs`,
+/// returning how many were removed.
+///
+/// Bundles merged from several sources often hold the same root more than once, which some
+/// certificate stores reject.  The first of each is kept, so the order is otherwise
+/// unchanged.
+///
+/// ```
+/// use rustls_pemfile::{dedup_certs, PemBundle};
+///
+/// let chain = include_bytes!("../tests/data/certificate.chain.pem");
+/// let merged = [&chain[..], &chain[..]].concat();
+/// let mut bundle = PemBundle::from_slice(&merged).unwrap();
+/// assert_eq!(dedup_certs(&mut bundle.certs), 3);
+/// assert_eq!(bundle.certs.len(), 3);
+/// ```
+pub fn dedup_certs(certs: &mut Vec<CertificateDer<'_>>) -> usize {
+    // a stable sort keeps equal certificates in their original order, so each after the
+    // first of its run is a duplicate
+    let mut order = (0..certs.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| certs[a].as_ref().cmp(certs[b].as_ref()));
+    let mut keep = vec![true; certs.len()];
+    for pair in order.windows(2) {
+        if certs[pair[0]] == certs[pair[1]] {
+            keep[pair[1]] = false;
+        }
+    }
+
+    let before = certs.len();
+    let mut keep = keep.into_iter();
+    certs.retain(|_| keep.next().unwrap_or(true));
+    before - certs.len()
+}
@@ -0,0 +1,89 @@
+use core::convert::TryFrom;
+use core::fmt;
+
+use pki_types::{
+    CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, PrivateKeyDer,
+    PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
+};
+
+use crate::pemfile::Item;
+
+/// An [`Item`] that could not be converted into another type, because it is of another
+/// kind.
+///
+/// This is the error of the `TryFrom<Item>` implementations for [`pki_types`] types, and
+/// gives the item back.
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use pki_types::CertificateDer;
+///
+/// let pem = include_bytes!("../tests/data/crl.pem");
+/// let (item, _) = rustls_pemfile::read_one_from_slice(pem).unwrap().unwrap();
+/// let error = CertificateDer::try_from(item).unwrap_err();
+/// assert_eq!(error.to_string(), r#"expected certificate, but found "X509 CRL" section"#);
+/// ```
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+pub struct UnexpectedItem {
+    /// The item that was found.
+    pub item: Item,
+    /// What was expected: such as "certificate".
+    pub expected: &'static str,
+}
+
+impl fmt::Display for UnexpectedItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {}, but found {:?} section",
+            self.expected,
+            self.item.label()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnexpectedItem {}
+
+macro_rules! try_from_item {
+    ($ty:ident, $name:literal, $variant:ident) => {
+        impl TryFrom<Item> for $ty<'static> {
+            type Error = UnexpectedItem;
+
+            fn try_from(item: Item) -> Result<Self, Self::Error> {
+                match item {
+                    Item::$variant(der) => Ok(der),
+                    item => Err(UnexpectedItem {
+                        item,
+                        expected: $name,
+                    }),
+                }
+            }
+        }
+    };
+}
+
+try_from_item!(CertificateDer, "certificate", X509Certificate);
+try_from_item!(SubjectPublicKeyInfoDer, "public key", SubjectPublicKeyInfo);
+try_from_item!(PrivatePkcs1KeyDer, "RSA private key", Pkcs1Key);
+try_from_item!(PrivatePkcs8KeyDer, "PKCS#8 private key", Pkcs8Key);
+try_from_item!(PrivateSec1KeyDer, "SEC1 private key", Sec1Key);
+try_from_item!(CertificateRevocationListDer, "CRL", Crl);
+try_from_item!(CertificateSigningRequestDer, "CSR", Csr);
+
+impl TryFrom<Item> for PrivateKeyDer<'static> {
+    type Error = UnexpectedItem;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        match item {
+            Item::Pkcs1Key(key) => Ok(key.into()),
+            Item::Pkcs8Key(key) => Ok(key.into()),
+            Item::Sec1Key(key) => Ok(key.into()),
+            item => Err(UnexpectedItem {
+                item,
+                expected: "private key",
+            }),
+        }
+    }
+}
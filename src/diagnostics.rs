@@ -0,0 +1,178 @@
+use core::fmt;
+use core::ops::Range;
+use std::borrow::Cow;
+use std::string::{String, ToString};
+
+use annotate_snippets::{Level, Renderer, Snippet};
+
+use crate::pemfile::{Error, Position};
+
+/// An [`Error`] rendered as an annotated snippet of the input it was found in, in the style
+/// of compiler diagnostics.
+///
+/// The line where the error was found is shown with the offending bytes underlined, along
+/// with the BEGIN line of the section it is within.  This shows the input around the error
+/// whether or not it is secret, so is for showing users their own input rather than for
+/// logs: see [`ParserOptions::redact()`][crate::ParserOptions::redact()].
+///
+/// ```
+/// use rustls_pemfile::{ErrorSnippet, ParserOptions};
+///
+/// let input = b"-----BEGIN CERTIFICATE-----\nMII%\n-----END CERTIFICATE-----\n";
+/// let error = ParserOptions::new().read_one_from_slice(input).unwrap_err();
+/// let rendered = ErrorSnippet::new(&error, input).origin("cert.pem").to_string();
+/// assert!(rendered.contains("--> cert.pem:2:4"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ErrorSnippet<'a> {
+    error: &'a Error,
+    input: &'a [u8],
+    origin: Option<&'a str>,
+    styled: bool,
+}
+
+impl<'a> ErrorSnippet<'a> {
+    /// Make a new `ErrorSnippet` for `error`, which was found in `input`.
+    ///
+    /// The error's positions must be relative to the start of `input`: so this is the
+    /// whole input that was read, not a remainder of it.
+    pub fn new(error: &'a Error, input: &'a [u8]) -> Self {
+        Self {
+            error,
+            input,
+            origin: None,
+            styled: false,
+        }
+    }
+
+    /// Name where the input came from, such as a file name, in the rendering.
+    pub fn origin(mut self, origin: &'a str) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Style the rendering with terminal colours.
+    ///
+    /// The default is plain text.
+    pub fn styled(mut self, styled: bool) -> Self {
+        self.styled = styled;
+        self
+    }
+}
+
+impl fmt::Display for ErrorSnippet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let renderer = match self.styled {
+            true => Renderer::styled(),
+            false => Renderer::plain(),
+        };
+        let title = self.error.to_string();
+        let position = self.error.position();
+
+        // only the lines from the start of the section to the error are rendered
+        let section = self
+            .error
+            .section_start()
+            .filter(|section| section.offset < position.offset);
+        let first = section.unwrap_or(position);
+        let start = line_start(self.input, first.offset);
+        let end = line_end(self.input, position.offset);
+        let source = text(&self.input[start..end]);
+
+        let primary = self.underline(position);
+        let label = self.label();
+        let mut snippet = Snippet::source(&source)
+            .line_start(first.line)
+            .fold(true)
+            .annotation(
+                Level::Error
+                    .span(primary.start - start..primary.end - start)
+                    .label(&label),
+            );
+        if let Some(section) = section {
+            let begin = section.offset..line_end(self.input, section.offset);
+            snippet = snippet.annotation(
+                Level::Info
+                    .span(begin.start - start..begin.end - start)
+                    .label("in the section starting here"),
+            );
+        }
+        if let Some(origin) = self.origin {
+            snippet = snippet.origin(origin);
+        }
+
+        let rendered = renderer.render(Level::Error.title(&title).snippet(snippet));
+        rendered.fmt(f)
+    }
+}
+
+impl ErrorSnippet<'_> {
+    /// The bytes of the input to underline for an error at `position`.
+    fn underline(&self, position: Position) -> Range<usize> {
+        let offset = position.offset.min(self.input.len());
+        let end = match self.error {
+            Error::Base64Decode { .. } => offset + 1,
+            _ => line_end(self.input, offset),
+        };
+        offset..end.min(self.input.len())
+    }
+
+    /// The label of the underlined bytes.
+    fn label(&self) -> String {
+        match self.error {
+            Error::MissingSectionEnd { .. } => "this section has no END line".into(),
+            Error::IllegalSectionStart { .. } => "this BEGIN line is malformed".into(),
+            Error::Base64Decode {
+                error: Some(error), ..
+            } => error.to_string(),
+            Error::Base64Decode { error: None, .. } => "invalid character".into(),
+            Error::NestedSectionStart { .. } => "this BEGIN line is within a section".into(),
+            Error::TextOutsideSection { .. } => "this text is outside any section".into(),
+            Error::UnexpectedWhitespace { .. } => "this line contains whitespace".into(),
+            Error::InvalidLineLength { .. } => "this line is not 64 characters".into(),
+            Error::NonCanonicalPadding { .. } => "this section's padding is not canonical".into(),
+            Error::InvalidArmorChecksum { .. } => "this section's checksum is wrong".into(),
+            Error::InvalidTrustedCertificate { .. } => "this section is malformed".into(),
+            Error::SectionTooLarge { .. } => "the limit on section size is exceeded here".into(),
+            Error::LineTooLong { .. } => "this line is too long".into(),
+            Error::TooManySections { .. } => "this section exceeds the limit".into(),
+            Error::DecodedBudgetExceeded { .. } => "this section exceeds the limit".into(),
+            Error::MissingSection { .. } => "no section was found by here".into(),
+            Error::UnexpectedSection { .. } => "only one section was expected".into(),
+        }
+    }
+}
+
+/// The offset of the start of the line containing `offset` in `input`.
+fn line_start(input: &[u8], offset: usize) -> usize {
+    let offset = offset.min(input.len());
+    input[..offset]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |index| index + 1)
+}
+
+/// The offset of the end of the line containing `offset` in `input`, before any line ending.
+fn line_end(input: &[u8], offset: usize) -> usize {
+    let offset = offset.min(input.len());
+    input[offset..]
+        .iter()
+        .position(|&b| matches!(b, b'\n' | b'\r'))
+        .map_or(input.len(), |index| offset + index)
+}
+
+/// `input` as text, keeping the offsets of its bytes.
+fn text(input: &[u8]) -> Cow<'_, str> {
+    match core::str::from_utf8(input) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(
+            input
+                .iter()
+                .map(|&b| match b.is_ascii() {
+                    true => b as char,
+                    false => '?',
+                })
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockEncryptMut, KeyIvInit};
+use pki_types::PrivatePkcs8KeyDer;
+
+use crate::trust::write_tlv;
+use crate::writer::WriteOptions;
+
+/// Encrypt `key` with `password`, returning it as an "ENCRYPTED PRIVATE KEY" PEM section.
+///
+/// The key is encrypted as specified in RFC 5958 using PBES2, with PBKDF2-HMAC-SHA256
+/// (600,000 iterations and a random 16-byte salt) and AES-256-CBC.  This is readable by
+/// OpenSSL and most other tools that accept password-protected PKCS#8 keys.
+///
+/// An error is returned only if the operating system's random number generator fails.
+pub fn encrypt_pkcs8_key(
+    key: &PrivatePkcs8KeyDer<'_>,
+    password: &[u8],
+    options: &WriteOptions,
+) -> Result<String, EncryptError> {
+    let mut salt = [0u8; 16];
+    let mut iv = [0u8; 16];
+    getrandom::getrandom(&mut salt)
+        .and_then(|()| getrandom::getrandom(&mut iv))
+        .map_err(|_| EncryptError::RandomnessUnavailable)?;
+
+    let der = encrypt(key.secret_pkcs8_der(), password, ITERATIONS, &salt, &iv);
+    Ok(options.encode_der("ENCRYPTED PRIVATE KEY", &der))
+}
+
+/// An error from [`encrypt_pkcs8_key()`].
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+pub enum EncryptError {
+    /// the operating system's random number generator failed
+    RandomnessUnavailable,
+}
+
+impl fmt::Display for EncryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RandomnessUnavailable => f.write_str("random number generator unavailable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncryptError {}
+
+/// Build the DER encoding of an `EncryptedPrivateKeyInfo` holding `plaintext`.
+pub(crate) fn encrypt(
+    plaintext: &[u8],
+    password: &[u8],
+    iterations: u32,
+    salt: &[u8; 16],
+    iv: &[u8; 16],
+) -> Vec<u8> {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password, salt, iterations, &mut key);
+
+    let mut encrypted = plaintext.to_vec();
+    encrypted.resize(plaintext.len() + 16, 0);
+    let len = cbc::Encryptor::<aes::Aes256>::new(&key.into(), iv.into())
+        .encrypt_padded_mut::<Pkcs7>(&mut encrypted, plaintext.len())
+        .expect("output has room for padding")
+        .len();
+    encrypted.truncate(len);
+    key.fill(0);
+
+    // PBKDF2-params ::= SEQUENCE {
+    //     salt OCTET STRING,
+    //     iterationCount INTEGER,
+    //     prf AlgorithmIdentifier DEFAULT hmacWithSHA1
+    // }
+    let mut kdf_params = Vec::new();
+    write_tlv(&mut kdf_params, OCTET_STRING, salt);
+    write_tlv(&mut kdf_params, INTEGER, &integer(iterations));
+    write_tlv(
+        &mut kdf_params,
+        SEQUENCE,
+        &[&oid(HMAC_WITH_SHA256)[..], &[NULL, 0]].concat(),
+    );
+
+    let mut kdf = oid(PBKDF2);
+    write_tlv(&mut kdf, SEQUENCE, &kdf_params);
+
+    let mut scheme = oid(AES_256_CBC);
+    write_tlv(&mut scheme, OCTET_STRING, iv);
+
+    let mut pbes2_params = Vec::new();
+    write_tlv(&mut pbes2_params, SEQUENCE, &kdf);
+    write_tlv(&mut pbes2_params, SEQUENCE, &scheme);
+
+    let mut algorithm = oid(PBES2);
+    write_tlv(&mut algorithm, SEQUENCE, &pbes2_params);
+
+    // EncryptedPrivateKeyInfo ::= SEQUENCE {
+    //     encryptionAlgorithm AlgorithmIdentifier,
+    //     encryptedData OCTET STRING
+    // }
+    let mut info = Vec::new();
+    write_tlv(&mut info, SEQUENCE, &algorithm);
+    write_tlv(&mut info, OCTET_STRING, &encrypted);
+
+    let mut out = Vec::new();
+    write_tlv(&mut out, SEQUENCE, &info);
+    out
+}
+
+fn oid(contents: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_tlv(&mut out, OBJECT_IDENTIFIER, contents);
+    out
+}
+
+/// The contents octets of a DER INTEGER with the value `n`.
+fn integer(n: u32) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count().min(3);
+    let mut out = Vec::new();
+    if bytes[skip] & 0x80 != 0 {
+        out.push(0);
+    }
+    out.extend_from_slice(&bytes[skip..]);
+    out
+}
+
+/// The PBKDF2 iteration count, as recommended by OWASP for PBKDF2-HMAC-SHA256.
+const ITERATIONS: u32 = 600_000;
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+
+/// 1.2.840.113549.1.5.13
+const PBES2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+/// 1.2.840.113549.1.5.12
+const PBKDF2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0c];
+/// 1.2.840.113549.2.9
+const HMAC_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x09];
+/// 2.16.840.1.101.3.4.1.42
+const AES_256_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
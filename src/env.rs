@@ -0,0 +1,91 @@
+use core::fmt;
+use std::env;
+use std::io;
+use std::string::String;
+use std::vec::Vec;
+
+use pki_types::{CertificateDer, PrivateKeyDer};
+
+use crate::pemfile::ReadError;
+use crate::reader::{ParserOptions, PemReader};
+
+/// Read the certificates in the environment variable `name`.
+///
+/// This is as for [`certs_from_file()`][crate::certs_from_file()], but for PEM passed in
+/// the environment, as is common for containers.  Line breaks may be real, or escaped as
+/// `\n`, as they are when the value was written on one line: see
+/// [`ParserOptions::unescape_newlines()`].  If the variable is not set, the error is
+/// [`ReadError::Io`] of kind [`io::ErrorKind::NotFound`].
+///
+/// ```
+/// # std::env::set_var("TLS_CA_BUNDLE", "-----BEGIN CERTIFICATE-----\\nAQID\\n-----END CERTIFICATE-----\\n");
+/// let certs = rustls_pemfile::certs_from_env("TLS_CA_BUNDLE").unwrap();
+/// assert_eq!(certs.len(), 1);
+/// ```
+pub fn certs_from_env(name: &str) -> Result<Vec<CertificateDer<'static>>, EnvError> {
+    read_env(name, |rd| rd.certs().collect())
+}
+
+/// Read the first private key in the environment variable `name`.
+///
+/// This is as for [`certs_from_env()`], but for the key of
+/// [`private_key()`][crate::private_key()]; and if there is no key, the error is
+/// [`ReadError::NotFound`].
+pub fn private_key_from_env(name: &str) -> Result<PrivateKeyDer<'static>, EnvError> {
+    read_env(name, |rd| {
+        rd.private_key()?.ok_or(ReadError::NotFound {
+            expected: "private key",
+        })
+    })
+}
+
+/// Read the environment variable `name`, and `read` it.
+fn read_env<T>(
+    name: &str,
+    read: impl FnOnce(PemReader<&[u8]>) -> Result<T, ReadError>,
+) -> Result<T, EnvError> {
+    let options = ParserOptions::new().unescape_newlines(true);
+    env::var(name)
+        .map_err(|error| ReadError::Io(io::Error::new(io::ErrorKind::NotFound, error)))
+        .and_then(|value| read(PemReader::with_options(value.as_bytes(), options)))
+        .map_err(|error| EnvError {
+            name: name.into(),
+            error,
+        })
+}
+
+/// An error reading an environment variable, with its name: see [`certs_from_env()`].
+///
+/// This is displayed as the name followed by the error.  The variable's value is not
+/// included, as it may be a private key.
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct EnvError {
+    /// The name of the variable.
+    pub name: String,
+    /// The error reading it.
+    pub error: ReadError,
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.error)
+    }
+}
+
+impl std::error::Error for EnvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// For callers that return `io::Error`, keeping the kind of an unset variable.
+impl From<EnvError> for io::Error {
+    fn from(error: EnvError) -> Self {
+        let kind = match &error.error {
+            ReadError::Io(error) => error.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
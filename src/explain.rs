@@ -0,0 +1,148 @@
+use core::fmt;
+
+use crate::pemfile::{is_encrypted, Error, Item, PemSection};
+
+/// Advice on fixing an error, or on converting an item into a form that is more widely
+/// supported.
+///
+/// This is returned by [`Error::explain()`], [`Item::explain()`] and
+/// [`PemSection::explain()`].  Commands name their input `in.pem` and their output
+/// `out.pem`.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hint {
+    /// What is likely to be wrong, and what to do about it.
+    pub advice: &'static str,
+    /// A command that converts the input into a supported form, if there is one.
+    pub command: Option<&'static str>,
+}
+
+impl Hint {
+    const fn new(advice: &'static str, command: Option<&'static str>) -> Self {
+        Self { advice, command }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.advice)?;
+        match self.command {
+            Some(command) => write!(f, "; try `{}`", command),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error {
+    /// Advice on fixing this error, if there is any.
+    pub fn explain(&self) -> Option<Hint> {
+        Some(match self {
+            Self::MissingSectionEnd { .. } => Hint::new(
+                "the input may have been truncated; check that it was copied in full",
+                None,
+            ),
+            Self::IllegalSectionStart { .. } => Hint::new(
+                "a BEGIN line must be `-----BEGIN <label>-----`, with five hyphens either side",
+                None,
+            ),
+            Self::Base64Decode { .. } | Self::InvalidArmorChecksum { .. } => Hint::new(
+                "the section has been corrupted, perhaps by an editor or by copying it from a \
+                 document; obtain it again",
+                None,
+            ),
+            Self::NestedSectionStart { .. } => Hint::new(
+                "the section before this BEGIN line is missing its END line",
+                None,
+            ),
+            Self::TextOutsideSection { .. } => Hint::new(
+                "remove the text between sections, or do not require strict RFC 7468 input",
+                None,
+            ),
+            Self::UnexpectedWhitespace { .. }
+            | Self::InvalidLineLength { .. }
+            | Self::NonCanonicalPadding { .. } => Hint::new(
+                "the section is not formatted as RFC 7468 requires; rewrite it with \
+                 `normalize()`, or do not require strict RFC 7468 input",
+                None,
+            ),
+            Self::InvalidTrustedCertificate { .. } => Hint::new(
+                "the trust settings are malformed; remove them to leave a plain certificate",
+                Some("openssl x509 -in in.pem -out out.pem"),
+            ),
+            Self::SectionTooLarge { .. }
+            | Self::LineTooLong { .. }
+            | Self::TooManySections { .. }
+            | Self::DecodedBudgetExceeded { .. } => Hint::new(
+                "the input exceeds a limit set in the `ParserOptions`; raise the limit if the \
+                 input is trusted",
+                None,
+            ),
+            Self::MissingSection { .. } | Self::UnexpectedSection { .. } => Hint::new(
+                "exactly one section is expected, from `-----BEGIN <label>-----` to \
+                 `-----END <label>-----`; check that the right value was given",
+                None,
+            ),
+        })
+    }
+}
+
+impl Item {
+    /// Advice on converting this item into a form that is more widely supported, if it is
+    /// not one already.
+    ///
+    /// This covers keys and bundles that TLS libraries commonly do not accept, such as
+    /// OpenSSH and DSA private keys, encrypted keys and PKCS#7 bundles.
+    pub fn explain(&self) -> Option<Hint> {
+        Some(match self {
+            Self::OpenSshKey(_) => Hint::new(
+                "this is an OpenSSH private key; convert it to PKCS#8 (note that this \
+                 rewrites the file in place, and is not possible for Ed25519 keys)",
+                Some("ssh-keygen -p -N \"\" -m PKCS8 -f in.pem"),
+            ),
+            Self::Ssh2PublicKey { .. } => Hint::new(
+                "this is an SSH2 public key; convert it to OpenSSH format, and then to PKCS#8",
+                Some("ssh-keygen -i -m RFC4716 -f in.pem"),
+            ),
+            Self::DsaKey(_) => Hint::new(
+                "DSA keys are obsolete and rarely supported; generate a new key, such as an \
+                 ECDSA key",
+                Some("openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out out.pem"),
+            ),
+            Self::Pkcs7(_) => Hint::new(
+                "this is a PKCS#7 bundle; extract its certificates",
+                Some("openssl pkcs7 -print_certs -in in.pem -out out.pem"),
+            ),
+            Self::TrustedCertificate { .. } => Hint::new(
+                "this certificate has OpenSSL trust settings; remove them to leave a plain \
+                 certificate",
+                Some("openssl x509 -in in.pem -out out.pem"),
+            ),
+            Self::LegacyEncrypted { .. } => Hint::new(
+                "this private key is encrypted with a legacy scheme; decrypt it",
+                Some("openssl pkey -in in.pem -out out.pem"),
+            ),
+            Self::Unknown { label, .. } if &label[..] == b"ENCRYPTED PRIVATE KEY" => Hint::new(
+                "this is an encrypted PKCS#8 private key; decrypt it",
+                Some("openssl pkey -in in.pem -out out.pem"),
+            ),
+            _ => return None,
+        })
+    }
+}
+
+impl PemSection {
+    /// Advice on converting this section into a form that is more widely supported, if it is
+    /// not one already.
+    ///
+    /// This is as for [`Item::explain()`], but also covers sections with labels that are not
+    /// recognised, but that are encrypted according to their RFC 1421 headers.
+    pub fn explain(&self) -> Option<Hint> {
+        match is_encrypted(&self.headers) {
+            true => Some(Hint::new(
+                "this private key is encrypted with a legacy scheme; decrypt it",
+                Some("openssl pkey -in in.pem -out out.pem"),
+            )),
+            false => self.item.explain(),
+        }
+    }
+}
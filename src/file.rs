@@ -0,0 +1,272 @@
+use alloc::collections::BTreeSet;
+use core::fmt;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::vec::Vec;
+use std::{format, process};
+
+use pki_types::{CertificateDer, CertificateRevocationListDer, PrivateKeyDer};
+
+use crate::pemfile::{Item, ReadError};
+use crate::reader::PemReader;
+use crate::writer::WriteOptions;
+
+/// Read the certificates in the file at `path`.
+///
+/// This is as for [`certs()`][crate::certs()], but collecting the certificates, and giving
+/// `path` in any error.
+pub fn certs_from_file(path: impl AsRef<Path>) -> Result<Vec<CertificateDer<'static>>, FileError> {
+    read_file(path.as_ref(), |rd| rd.certs().collect())
+}
+
+/// Read the certificate revocation lists (CRLs) in the file at `path`.
+///
+/// This is as for [`crls()`][crate::crls()], but collecting the CRLs, and giving `path` in
+/// any error.
+pub fn crls_from_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<CertificateRevocationListDer<'static>>, FileError> {
+    read_file(path.as_ref(), |rd| rd.crls().collect())
+}
+
+/// Read the first private key in the file at `path`.
+///
+/// This is as for [`private_key()`][crate::private_key()], but giving `path` in any error;
+/// and if there is no key, the error is [`ReadError::NotFound`].
+pub fn private_key_from_file(path: impl AsRef<Path>) -> Result<PrivateKeyDer<'static>, FileError> {
+    read_file(path.as_ref(), |rd| {
+        rd.private_key()?.ok_or(ReadError::NotFound {
+            expected: "private key",
+        })
+    })
+}
+
+/// Read the certificates in the files in the directory `dir`, giving the path of the file
+/// each came from.
+///
+/// This is for directories of trusted certificates, as given to OpenSSL with `-CApath`.
+/// The files read are those named `*.pem` or `*.crt`, and those in OpenSSL's hashed layout,
+/// named `HASH.N` for a subject name hash of eight hex digits and a number, such as
+/// `9d66eef0.0`.  Other files, and subdirectories, are passed over.  Symbolic links are
+/// followed, and a file linked to under several names, as `c_rehash` does, is read once.
+/// Files are read in the order of their names, and an error in any of them is returned,
+/// with its path.
+pub fn certs_from_dir(
+    dir: impl AsRef<Path>,
+) -> Result<Vec<(PathBuf, CertificateDer<'static>)>, FileError> {
+    let dir = dir.as_ref();
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(with_path(dir))? {
+        let path = entry.map_err(with_path(dir))?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        if (is_cert_file(name) || is_hashed_name(name)) && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let (mut certs, mut seen) = (Vec::new(), BTreeSet::new());
+    for path in paths {
+        if !seen.insert(fs::canonicalize(&path).map_err(with_path(&path))?) {
+            continue;
+        }
+        for cert in certs_from_file(&path)? {
+            certs.push((path.clone(), cert));
+        }
+    }
+
+    Ok(certs)
+}
+
+/// Attribute an I/O error to `path`.
+fn with_path(path: &Path) -> impl FnOnce(io::Error) -> FileError + '_ {
+    move |error| FileError {
+        path: path.to_path_buf(),
+        error: error.into(),
+    }
+}
+
+/// Whether `name` has an extension used for PEM certificates.
+fn is_cert_file(name: &str) -> bool {
+    name.ends_with(".pem") || name.ends_with(".crt")
+}
+
+/// Whether `name` is in OpenSSL's hashed layout for certificates, such as `9d66eef0.0`.
+fn is_hashed_name(name: &str) -> bool {
+    match name.split_once('.') {
+        Some((hash, n)) => {
+            hash.len() == 8
+                && hash.bytes().all(|b| b.is_ascii_hexdigit())
+                && !n.is_empty()
+                && n.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// Open the file at `path`, and `read` it.
+fn read_file<T>(
+    path: &Path,
+    read: impl FnOnce(PemReader<BufReader<File>>) -> Result<T, ReadError>,
+) -> Result<T, FileError> {
+    File::open(path)
+        .map_err(ReadError::from)
+        .and_then(|file| read(PemReader::new(BufReader::new(file))))
+        .map_err(|error| FileError {
+            path: path.to_path_buf(),
+            error,
+        })
+}
+
+/// An error reading a file, with its path: see [`certs_from_file()`].
+///
+/// This is displayed as the path followed by the error, which gives where in the file any
+/// problem with its contents is.
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct FileError {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The error reading it.
+    pub error: ReadError,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// For callers that return `io::Error`, keeping the kind of any error opening the file.
+impl From<FileError> for io::Error {
+    fn from(error: FileError) -> Self {
+        let kind = match &error.error {
+            ReadError::Io(error) => error.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// Encode each of `items` as PEM, and atomically replace the file at `path` with them.
+///
+/// The items are written to a temporary file in the same directory, which is flushed to
+/// disk and then renamed over `path`; so readers of `path` see either its old contents
+/// or all of the new ones, never a partly-written file.
+///
+/// On Unix, if any of `items` holds private key material (or if
+/// [`FileOptions::restrict_permissions()`] is set) the file is created readable and
+/// writable only by its owner (mode 0600) before anything is written to it.
+pub fn write_pem_file<'a>(
+    path: impl AsRef<Path>,
+    items: impl IntoIterator<Item = &'a Item>,
+    options: &FileOptions,
+) -> Result<(), io::Error> {
+    let path = path.as_ref();
+    let items = items.into_iter().collect::<Vec<_>>();
+    let restrict = options.restrict || items.iter().any(|item| item.is_secret());
+
+    let temp = temp_path(path)?;
+    let result =
+        write_temp(&temp, &items, &options.write, restrict).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+
+    // make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = match dir.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => dir,
+        };
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Options for [`write_pem_file()`].
+#[derive(Clone, Debug, Default)]
+pub struct FileOptions {
+    write: WriteOptions,
+    restrict: bool,
+}
+
+impl FileOptions {
+    /// Make a new `FileOptions` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`WriteOptions`] used to encode each item.
+    pub fn write_options(mut self, options: WriteOptions) -> Self {
+        self.write = options;
+        self
+    }
+
+    /// Restrict the file's permissions even if none of the items hold private key material.
+    ///
+    /// This has no effect on platforms other than Unix.
+    pub fn restrict_permissions(mut self, restrict: bool) -> Self {
+        self.restrict = restrict;
+        self
+    }
+}
+
+fn write_temp(
+    temp: &Path,
+    items: &[&Item],
+    options: &WriteOptions,
+    restrict: bool,
+) -> Result<(), io::Error> {
+    let mut open = OpenOptions::new();
+    open.write(true).create_new(true);
+    #[cfg(unix)]
+    if restrict {
+        use std::os::unix::fs::OpenOptionsExt;
+        open.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = restrict;
+
+    let mut file = open.open(temp)?;
+    options.write_all(&mut file, items.iter().copied())?;
+    file.flush()?;
+    file.sync_all()
+}
+
+/// A path for a temporary file alongside `path`, which this process has not used before.
+fn temp_path(path: &Path) -> Result<PathBuf, io::Error> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} does not name a file", path),
+        )
+    })?;
+
+    let mut temp = OsString::from(".");
+    temp.push(name);
+    temp.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(path.with_file_name(temp))
+}
@@ -0,0 +1,137 @@
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use pki_types::{
+    CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, PrivateKeyDer,
+    PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
+};
+
+use crate::pemfile::{Item, ReadError};
+use crate::reader::PemReader;
+
+/// Decoding of [`pki_types`] DER types from PEM.
+///
+/// This is the counterpart of [`ToPem`][crate::ToPem]: each type is read from the sections
+/// with the labels it would be written with, and other sections are passed over.  So the
+/// certificates in a file are, in one line:
+///
+/// ```no_run
+/// use rustls_pemfile::FromPem;
+/// use pki_types::CertificateDer;
+///
+/// let certs = CertificateDer::pem_file_iter("chain.pem")?.collect::<Result<Vec<_>, _>>()?;
+/// # Ok::<(), rustls_pemfile::ReadError>(())
+/// ```
+pub trait FromPem: Sized {
+    /// What this type is, for [`ReadError::NotFound`]: such as "certificate".
+    const NAME: &'static str;
+
+    /// Take this type from `item`, giving `item` back if it is of another type.
+    fn from_item(item: Item) -> Result<Self, Item>;
+
+    /// Read the first section of this type from `pem`.
+    ///
+    /// The error is [`ReadError::NotFound`] if there is none.
+    fn from_pem_slice(pem: &[u8]) -> Result<Self, ReadError> {
+        Self::from_pem_reader(pem)
+    }
+
+    /// Parse exactly one section, of this type, from `pem`.
+    ///
+    /// This is as for [`Item::from_str()`][core::str::FromStr::from_str()], but the error is
+    /// [`ReadError::NotFound`] if the section is of another type.
+    fn from_pem_str(pem: &str) -> Result<Self, ReadError> {
+        Self::from_item(pem.parse()?).map_err(|_| ReadError::NotFound {
+            expected: Self::NAME,
+        })
+    }
+
+    /// Read the first section of this type from `rd`.
+    ///
+    /// The error is [`ReadError::NotFound`] if there is none.
+    fn from_pem_reader(rd: impl io::BufRead) -> Result<Self, ReadError> {
+        Self::pem_reader_iter(rd)
+            .next()
+            .unwrap_or(Err(ReadError::NotFound {
+                expected: Self::NAME,
+            }))
+    }
+
+    /// Read the first section of this type from the file at `path`.
+    ///
+    /// The error is [`ReadError::NotFound`] if there is none.
+    fn from_pem_file(path: impl AsRef<Path>) -> Result<Self, ReadError> {
+        Self::from_pem_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Iterate over the sections of this type in `rd`.
+    fn pem_reader_iter<R: io::BufRead>(rd: R) -> FromPemIter<R, Self> {
+        FromPemIter {
+            reader: PemReader::new(rd),
+            item: PhantomData,
+        }
+    }
+
+    /// Iterate over the sections of this type in the file at `path`.
+    fn pem_file_iter(
+        path: impl AsRef<Path>,
+    ) -> Result<FromPemIter<BufReader<File>, Self>, ReadError> {
+        Ok(Self::pem_reader_iter(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// An iterator over the items of one type read from PEM: see [`FromPem::pem_reader_iter()`].
+pub struct FromPemIter<R, T> {
+    reader: PemReader<R>,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<R: io::BufRead, T: FromPem> Iterator for FromPemIter<R, T> {
+    type Item = Result<T, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_one() {
+                Ok(Some(item)) => match T::from_item(item) {
+                    Ok(item) => return Some(Ok(item)),
+                    Err(_) => continue,
+                },
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+impl<R: fmt::Debug, T> fmt::Debug for FromPemIter<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromPemIter")
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
+
+macro_rules! from_pem {
+    ($ty:ident, $name:literal) => {
+        impl FromPem for $ty<'static> {
+            const NAME: &'static str = $name;
+
+            fn from_item(item: Item) -> Result<Self, Item> {
+                Self::try_from(item).map_err(|error| error.item)
+            }
+        }
+    };
+}
+
+from_pem!(CertificateDer, "certificate");
+from_pem!(SubjectPublicKeyInfoDer, "public key");
+from_pem!(PrivatePkcs1KeyDer, "RSA private key");
+from_pem!(PrivatePkcs8KeyDer, "PKCS#8 private key");
+from_pem!(PrivateSec1KeyDer, "SEC1 private key");
+from_pem!(CertificateRevocationListDer, "CRL");
+from_pem!(CertificateSigningRequestDer, "CSR");
+from_pem!(PrivateKeyDer, "private key");
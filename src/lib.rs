@@ -6,18 +6,58 @@
 //! not decode the actual DER-encoded keys/certificates.
 //!
 //! ## Quick start
+//!
 //! Starting with an `io::BufRead` containing the file to be read:
-//! - Use `read_all()` to ingest the whole file, then work through the contents in-memory, or,
-//! - Use `read_one()` to stream through the file, processing the items as found, or,
-//! - Use `certs()` to extract just the certificates (silently discarding other sections), and
-//!   similarly for `rsa_private_keys()` and `pkcs8_private_keys()`.
+//! - Use `read_all()` to ingest the whole file, then work through the contents in-memory.
+//! - Use `PemBundle::from_reader()` to read the whole file into separate lists of
+//!   certificates, CRLs, keys and so on.
+//! - Use `read_one()` to stream through the file, processing the items as found.
+//! - Use `certs()` to extract just the certificates, silently discarding other sections.
+//!   `csrs()`, `public_keys()`, `rsa_private_keys()` and `pkcs8_private_keys()` do the same
+//!   for their kinds of item, and `private_keys()` for keys of any of the common kinds.
+//! - Use `identity()` for a certificate chain and its key from one file.
+//!   `identity_strict()` rejects a file with more than one key.
+//! - These helpers report errors as a `ReadError`, which distinguishes I/O failures,
+//!   malformed input and base64 corruption. `rsa_private_keys_strict()` and similar
+//!   instead fail if there are no keys of that kind, naming the kinds of key that were found.
+//! - The helpers take a `&mut dyn io::BufRead`. The same helpers are methods of
+//!   `PemReader`, which is generic over its reader. `certs_owned()` and similar take
+//!   ownership of the reader, for iterators that are `Send + 'static`.
+//! - Use the `FromPem` trait to read a type such as `CertificateDer` straight from a slice,
+//!   a reader or a file, with `CertificateDer::pem_file_iter()` and the like. An `Item`
+//!   already read converts into these types with `TryFrom`.
+//! - Use `certs_from_file()` and similar, whose errors give the file's path.
+//!   `certs_from_dir()` reads an OpenSSL-style directory of trusted certificates, and
+//!   `certs_from_env()` and `private_key_from_env()` read PEM in environment variables.
+//! - Use `scan()` to list where each section is without decoding any. `split_sections()`
+//!   passes the text of each section on, by label, to be written elsewhere.
+//! - Use a `PemReader` to read with non-default `ParserOptions`. Its `read_section()` reads
+//!   each item along with the section's headers. A `Parser` does the same without owning
+//!   the reader, and can be reused for many inputs without reallocating its buffers.
+//!
+//! With the opt-in "diagnostics" Cargo feature, an `ErrorSnippet` renders an `Error` as an
+//! annotated snippet of the input, for tools that report errors in user-provided PEM.
+//! The opt-in "simd" feature decodes the base64 of public items, such as certificates, with
+//! SIMD instructions where the CPU supports them.  Private keys are always decoded in
+//! constant time, without them.  The opt-in "mmap" feature adds the unsafe
+//! `from_path_mmap()`, which reads a file by memory-mapping it rather than through an
+//! `io::BufRead`.  The opt-in "parallel" feature adds `read_all_parallel()`, which decodes
+//! the sections of a large bundle across a rayon thread pool, and
+//! `ParserOptions::decode_in_parallel()`, which does the same for the body of a single large
+//! section.  The opt-in "serde" feature implements `Serialize` and `Deserialize` for `Item`,
+//! as a string of its PEM text, so that items can be carried in configuration and messages.
+//!
+//! Items can be written back out as PEM to an `io::Write` with `write_one()` or
+//! `write_all()`, and `normalize()` rewrites a whole file into canonical form.
 //!
 //! # no-std support
 //!
 //! The opt-out "std" Cargo feature can be disabled to put this crate in no-std mode.
 //!
-//! In no-std mode, the `read_one_from_slice` API can be used to parse a .pem file that has already
-//! been loaded into memory.
+//! In no-std mode, the `read_one_from_slice` API can be used to parse a .pem file that has
+//! already been loaded into memory. `read_all_from_slice` iterates over its items, from
+//! either end, and `read_all_borrowed` decodes all of it into one buffer. `write_one_fmt`
+//! and `write_one_to_slice` write items into a `core::fmt::Write` or a byte buffer.
 //!
 //! ## Example code
 #![cfg_attr(feature = "std", doc = "```")]
@@ -40,7 +80,10 @@
 //! ```
 
 // Require docs for public APIs, deny unsafe code, etc.
-#![forbid(unsafe_code, unused_must_use, unstable_features)]
+#![forbid(unused_must_use, unstable_features)]
+// memory-mapping a file is unsafe, so is only allowed in the `mmap` module
+#![cfg_attr(not(feature = "mmap"), forbid(unsafe_code))]
+#![cfg_attr(feature = "mmap", deny(unsafe_code))]
 #![deny(
     trivial_casts,
     trivial_numeric_casts,
@@ -59,16 +102,67 @@ extern crate std;
 #[cfg(feature = "std")]
 mod tests;
 
+pub mod base64;
+mod bundle;
+mod convert;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "crypto")]
+mod encrypt;
+#[cfg(feature = "std")]
+mod env;
+mod explain;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "std")]
+mod from_pem;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod pemfile;
+mod reader;
 #[cfg(feature = "std")]
-use core::iter;
+mod scan;
+#[cfg(feature = "serde")]
+mod serialize;
+mod trust;
+#[cfg(feature = "std")]
+mod utf16;
+mod warning;
+mod writer;
 /// --- Legacy APIs:
 #[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
+pub use bundle::{dedup_certs, PemBundle};
+pub use convert::UnexpectedItem;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::ErrorSnippet;
+#[cfg(feature = "crypto")]
+pub use encrypt::{encrypt_pkcs8_key, EncryptError};
 #[cfg(feature = "std")]
-pub use pemfile::{read_all, read_one};
-pub use pemfile::{read_one_from_slice, Error, Item};
+pub use env::{certs_from_env, private_key_from_env, EnvError};
+pub use explain::Hint;
+#[cfg(feature = "std")]
+pub use file::{
+    certs_from_dir, certs_from_file, crls_from_file, private_key_from_file, write_pem_file,
+    FileError, FileOptions,
+};
+#[cfg(feature = "std")]
+pub use from_pem::{FromPem, FromPemIter};
+#[cfg(feature = "mmap")]
+pub use mmap::from_path_mmap;
+#[cfg(feature = "parallel")]
+pub use parallel::read_all_parallel;
+#[cfg(feature = "std")]
+pub use pemfile::{read_all, read_all_with_spans, read_one, read_one_with_span, ReadError};
+pub use pemfile::{
+    read_all_borrowed, read_all_from_slice, read_one_from_slice, BorrowedItem, Error, Item,
+    PemSection, Position, Span,
+};
 #[cfg(feature = "std")]
 use pki_types::PrivateKeyDer;
 #[cfg(feature = "std")]
@@ -76,41 +170,121 @@ use pki_types::{
     CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, PrivatePkcs1KeyDer,
     PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
 };
+#[cfg(feature = "std")]
+pub use reader::{
+    CertIter, CrlIter, CsrIter, EcPrivateKeyIter, KindIter, Parser, PemReader, Pkcs8PrivateKeyIter,
+    PrivateKeyIter, PublicKeyIter, RsaPrivateKeyIter, SectionsMatching,
+};
+pub use reader::{ParserOptions, Redaction, Report, SliceReader};
+#[cfg(feature = "std")]
+pub use scan::{scan, split_sections, SectionInfo};
+pub use trust::CertificateTrust;
+pub use warning::Warning;
+#[cfg(feature = "std")]
+pub use writer::{normalize, wrap_der, write_all, write_one, SectionWriter};
+pub use writer::{
+    write_one_fmt, write_one_to_slice, BundleWriter, LineEnding, ToPem, WriteOptions,
+};
 
 /// Return an iterator over certificates from `rd`.
 ///
 /// Filters out any PEM sections that are not certificates and yields errors if a problem
 /// occurs while trying to extract a certificate.
+///
+/// Other sections are skipped line by line, without being decoded: so a large private key
+/// before a certificate chain costs little to read past, and any corruption within it is
+/// not reported.  The other helpers that read one kind of item do the same.
 #[cfg(feature = "std")]
-pub fn certs(
-    rd: &mut dyn io::BufRead,
-) -> impl Iterator<Item = Result<CertificateDer<'static>, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose()).filter_map(|item| match item {
-        Ok(Item::X509Certificate(cert)) => Some(Ok(cert)),
-        Err(err) => Some(Err(err)),
-        _ => None,
-    })
+pub fn certs(rd: &mut dyn io::BufRead) -> CertIter<'_> {
+    PemReader::new(rd).certs()
 }
 
 /// Return the first private key found in `rd`.
 ///
 /// Yields the first PEM section describing a private key (of any type), or an error if a
 /// problem occurs while trying to read PEM sections.
+///
+/// PKCS#1 ("RSA PRIVATE KEY"), PKCS#8 ("PRIVATE KEY") and SEC1 ("EC PRIVATE KEY") keys are
+/// returned as the matching variant of [`PrivateKeyDer`], so there is no need to try the
+/// iterators for each in turn.  Keys in other forms, such as OpenSSH keys or encrypted
+/// PKCS#8 keys, are passed over: see [`Item::explain()`] for converting them.
+///
+/// ```
+/// use pki_types::PrivateKeyDer;
+///
+/// let mut data = &include_bytes!("../tests/data/nistp256key.pem")[..];
+/// let key = rustls_pemfile::private_key(&mut data).unwrap().unwrap();
+/// assert!(matches!(key, PrivateKeyDer::Sec1(_)));
+/// ```
 #[cfg(feature = "std")]
-pub fn private_key(rd: &mut dyn io::BufRead) -> Result<Option<PrivateKeyDer<'static>>, io::Error> {
-    for result in iter::from_fn(move || read_one(rd).transpose()) {
-        match result? {
-            Item::Pkcs1Key(key) => return Ok(Some(key.into())),
-            Item::Pkcs8Key(key) => return Ok(Some(key.into())),
-            Item::Sec1Key(key) => return Ok(Some(key.into())),
-            Item::X509Certificate(_)
-            | Item::SubjectPublicKeyInfo(_)
-            | Item::Crl(_)
-            | Item::Csr(_) => continue,
-        }
-    }
+pub fn private_key(rd: &mut dyn io::BufRead) -> Result<Option<PrivateKeyDer<'static>>, ReadError> {
+    PemReader::new(rd).private_key()
+}
 
-    Ok(None)
+/// Return an iterator over the private keys in `rd`, of any of the kinds that
+/// [`private_key()`] returns.
+///
+/// This is for tools that process files holding several keys, perhaps of different
+/// kinds, such as during key rotation.  Other sections are filtered out, and errors are
+/// yielded as for [`certs()`].
+#[cfg(feature = "std")]
+pub fn private_keys(rd: &mut dyn io::BufRead) -> PrivateKeyIter<'_> {
+    PemReader::new(rd).private_keys()
+}
+
+/// Return the certificates in `rd`, and its first private key, reading it once.
+///
+/// This is for configuring a server from a single file holding its certificate chain and
+/// key, in any order.  The key is as for [`private_key()`]; if there is none, the error is
+/// [`ReadError::NotFound`].  The certificates are in the order read, which for a chain is
+/// normally the end-entity certificate first.
+///
+/// ```
+/// let mut data = &include_bytes!("../tests/data/zen.pem")[..];
+/// let (chain, _key) = rustls_pemfile::identity(&mut data).unwrap();
+/// assert_eq!(chain.len(), 4);
+/// ```
+#[cfg(feature = "std")]
+pub fn identity(
+    rd: &mut dyn io::BufRead,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ReadError> {
+    PemReader::new(rd).identity()
+}
+
+/// Return the certificates in `rd`, and its only private key, reading it once.
+///
+/// As for [`identity()`], except that the input must hold exactly one private key and at
+/// least one certificate.  A file with a second key, perhaps left behind during key
+/// rotation, is ambiguous: rather than the first key being used, the error is
+/// [`ReadError::MultipleKeys`], giving the line each key starts on.  If there are no
+/// certificates, the error is [`ReadError::NotFound`].
+#[cfg(feature = "std")]
+pub fn identity_strict(
+    rd: &mut dyn io::BufRead,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ReadError> {
+    PemReader::new(rd).identity_strict()
+}
+
+/// Return an iterator over the items in `rd` whose labels `predicate` accepts.
+///
+/// The predicate is given each item's [`Item::label()`]: so the canonical label of a
+/// recognised item, such as `CERTIFICATE` for one read as `X509 CERTIFICATE`, and the label
+/// as read of any other.  This is for tools that select sections by label, including
+/// unrecognised ones, rather than by [`Item`] variant.  Unlike the helpers that read one
+/// kind of item, every section is decoded, and errors are yielded as for [`certs()`].
+///
+/// ```
+/// // everything except private keys
+/// let mut data = &include_bytes!("../tests/data/zen.pem")[..];
+/// let public = rustls_pemfile::sections_matching(&mut data, |label| !label.ends_with("PRIVATE KEY"));
+/// assert_eq!(public.count(), 7);
+/// ```
+#[cfg(feature = "std")]
+pub fn sections_matching<F: FnMut(&str) -> bool>(
+    rd: &mut dyn io::BufRead,
+    predicate: F,
+) -> SectionsMatching<&mut dyn io::BufRead, F> {
+    PemReader::new(rd).sections_matching(predicate)
 }
 
 /// Return the first certificate signing request (CSR) found in `rd`.
@@ -120,20 +294,18 @@ pub fn private_key(rd: &mut dyn io::BufRead) -> Result<Option<PrivateKeyDer<'sta
 #[cfg(feature = "std")]
 pub fn csr(
     rd: &mut dyn io::BufRead,
-) -> Result<Option<CertificateSigningRequestDer<'static>>, io::Error> {
-    for result in iter::from_fn(move || read_one(rd).transpose()) {
-        match result? {
-            Item::Csr(csr) => return Ok(Some(csr)),
-            Item::Pkcs1Key(_)
-            | Item::Pkcs8Key(_)
-            | Item::Sec1Key(_)
-            | Item::X509Certificate(_)
-            | Item::SubjectPublicKeyInfo(_)
-            | Item::Crl(_) => continue,
-        }
-    }
+) -> Result<Option<CertificateSigningRequestDer<'static>>, ReadError> {
+    PemReader::new(rd).csr()
+}
 
-    Ok(None)
+/// Return an iterator over certificate signing requests (CSRs) from `rd`.
+///
+/// Filters out any PEM sections that are not CSRs and yields errors if a problem occurs
+/// while trying to extract a CSR.  Both "CERTIFICATE REQUEST" and the legacy "NEW
+/// CERTIFICATE REQUEST" labels are read.
+#[cfg(feature = "std")]
+pub fn csrs(rd: &mut dyn io::BufRead) -> CsrIter<'_> {
+    PemReader::new(rd).csrs()
 }
 
 /// Return an iterator certificate revocation lists (CRLs) from `rd`.
@@ -141,14 +313,8 @@ pub fn csr(
 /// Filters out any PEM sections that are not CRLs and yields errors if a problem occurs
 /// while trying to extract a CRL.
 #[cfg(feature = "std")]
-pub fn crls(
-    rd: &mut dyn io::BufRead,
-) -> impl Iterator<Item = Result<CertificateRevocationListDer<'static>, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose()).filter_map(|item| match item {
-        Ok(Item::Crl(crl)) => Some(Ok(crl)),
-        Err(err) => Some(Err(err)),
-        _ => None,
-    })
+pub fn crls(rd: &mut dyn io::BufRead) -> CrlIter<'_> {
+    PemReader::new(rd).crls()
 }
 
 /// Return an iterator over RSA private keys from `rd`.
@@ -156,14 +322,8 @@ pub fn crls(
 /// Filters out any PEM sections that are not RSA private keys and yields errors if a problem
 /// occurs while trying to extract an RSA private key.
 #[cfg(feature = "std")]
-pub fn rsa_private_keys(
-    rd: &mut dyn io::BufRead,
-) -> impl Iterator<Item = Result<PrivatePkcs1KeyDer<'static>, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose()).filter_map(|item| match item {
-        Ok(Item::Pkcs1Key(key)) => Some(Ok(key)),
-        Err(err) => Some(Err(err)),
-        _ => None,
-    })
+pub fn rsa_private_keys(rd: &mut dyn io::BufRead) -> RsaPrivateKeyIter<'_> {
+    PemReader::new(rd).rsa_private_keys()
 }
 
 /// Return an iterator over PKCS8-encoded private keys from `rd`.
@@ -171,14 +331,8 @@ pub fn rsa_private_keys(
 /// Filters out any PEM sections that are not PKCS8-encoded private keys and yields errors if a
 /// problem occurs while trying to extract an RSA private key.
 #[cfg(feature = "std")]
-pub fn pkcs8_private_keys(
-    rd: &mut dyn io::BufRead,
-) -> impl Iterator<Item = Result<PrivatePkcs8KeyDer<'static>, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose()).filter_map(|item| match item {
-        Ok(Item::Pkcs8Key(key)) => Some(Ok(key)),
-        Err(err) => Some(Err(err)),
-        _ => None,
-    })
+pub fn pkcs8_private_keys(rd: &mut dyn io::BufRead) -> Pkcs8PrivateKeyIter<'_> {
+    PemReader::new(rd).pkcs8_private_keys()
 }
 
 /// Return an iterator over SEC1-encoded EC private keys from `rd`.
@@ -186,27 +340,158 @@ pub fn pkcs8_private_keys(
 /// Filters out any PEM sections that are not SEC1-encoded EC private keys and yields errors if a
 /// problem occurs while trying to extract a SEC1-encoded EC private key.
 #[cfg(feature = "std")]
-pub fn ec_private_keys(
+pub fn ec_private_keys(rd: &mut dyn io::BufRead) -> EcPrivateKeyIter<'_> {
+    PemReader::new(rd).ec_private_keys()
+}
+
+/// Return the RSA private keys from `rd`, requiring at least one.
+///
+/// As for [`rsa_private_keys()`], except that if there are none, the error is
+/// [`ReadError::KeyNotFound`], giving the other kinds of private key found, so that the
+/// function that reads them can be called instead.
+#[cfg(feature = "std")]
+pub fn rsa_private_keys_strict(
+    rd: &mut dyn io::BufRead,
+) -> Result<Vec<PrivatePkcs1KeyDer<'static>>, ReadError> {
+    keys_strict(rd, "RSA PRIVATE KEY", |item| match item {
+        Item::Pkcs1Key(key) => Ok(key),
+        item => Err(item),
+    })
+}
+
+/// Return the PKCS8-encoded private keys from `rd`, requiring at least one.
+///
+/// As for [`rsa_private_keys_strict()`], but for the keys of [`pkcs8_private_keys()`].
+#[cfg(feature = "std")]
+pub fn pkcs8_private_keys_strict(
     rd: &mut dyn io::BufRead,
-) -> impl Iterator<Item = Result<PrivateSec1KeyDer<'static>, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose()).filter_map(|item| match item {
-        Ok(Item::Sec1Key(key)) => Some(Ok(key)),
-        Err(err) => Some(Err(err)),
-        _ => None,
+) -> Result<Vec<PrivatePkcs8KeyDer<'static>>, ReadError> {
+    keys_strict(rd, "PRIVATE KEY", |item| match item {
+        Item::Pkcs8Key(key) => Ok(key),
+        item => Err(item),
     })
 }
 
+/// Return the SEC1-encoded EC private keys from `rd`, requiring at least one.
+///
+/// As for [`rsa_private_keys_strict()`], but for the keys of [`ec_private_keys()`].
+#[cfg(feature = "std")]
+pub fn ec_private_keys_strict(
+    rd: &mut dyn io::BufRead,
+) -> Result<Vec<PrivateSec1KeyDer<'static>>, ReadError> {
+    keys_strict(rd, "EC PRIVATE KEY", |item| match item {
+        Item::Sec1Key(key) => Ok(key),
+        item => Err(item),
+    })
+}
+
+/// Collect the keys `extract` takes from the items in `rd`, or fail if there are none.
+#[cfg(feature = "std")]
+fn keys_strict<T>(
+    rd: &mut dyn io::BufRead,
+    expected: &'static str,
+    extract: impl Fn(Item) -> Result<T, Item>,
+) -> Result<Vec<T>, ReadError> {
+    let (mut keys, mut found) = (Vec::new(), Vec::new());
+    for item in read_all(rd) {
+        match extract(item?) {
+            Ok(key) => keys.push(key),
+            Err(item) => match item.private_key_label() {
+                Some(label) if !found.contains(&label) => found.push(label),
+                _ => {}
+            },
+        }
+    }
+
+    match keys.is_empty() {
+        true => Err(ReadError::KeyNotFound { expected, found }),
+        false => Ok(keys),
+    }
+}
+
 /// Return an iterator over SPKI-encoded keys from `rd`.
 ///
 /// Filters out any PEM sections that are not SPKI-encoded public keys and yields errors if a
 /// problem occurs while trying to extract a SPKI-encoded public key.
+///
+/// These are the "PUBLIC KEY" sections written by `openssl pkey -pubout`, which is the form
+/// used for key pinning.  PKCS#1 "RSA PUBLIC KEY" sections are not SPKI-encoded, so are
+/// filtered out: they are read as [`Item::Pkcs1PublicKey`].
+///
+/// ```
+/// let mut data = &include_bytes!("../tests/data/spki.pem")[..];
+/// let keys = rustls_pemfile::public_keys(&mut data).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(keys.len(), 1);
+/// ```
 #[cfg(feature = "std")]
-pub fn public_keys(
-    rd: &mut dyn io::BufRead,
-) -> impl Iterator<Item = Result<SubjectPublicKeyInfoDer<'static>, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose()).filter_map(|item| match item {
-        Ok(Item::SubjectPublicKeyInfo(key)) => Some(Ok(key)),
-        Err(err) => Some(Err(err)),
-        _ => None,
-    })
+pub fn public_keys(rd: &mut dyn io::BufRead) -> PublicKeyIter<'_> {
+    PemReader::new(rd).public_keys()
+}
+
+/// Return an iterator over certificates from `rd`, taking ownership of it.
+///
+/// As for [`certs()`], but the iterator does not borrow the reader: so if `rd` is `Send`
+/// and `'static`, such as a `BufReader<File>`, so is the iterator, which can then be moved
+/// into a spawned thread or task, or stored for later.
+#[cfg(feature = "std")]
+pub fn certs_owned<R: io::BufRead>(rd: R) -> KindIter<R, CertificateDer<'static>> {
+    PemReader::new(rd).certs()
+}
+
+/// Return an iterator over the private keys in `rd`, taking ownership of it.
+///
+/// As for [`certs_owned()`], but for the keys of [`private_keys()`].
+#[cfg(feature = "std")]
+pub fn private_keys_owned<R: io::BufRead>(rd: R) -> KindIter<R, PrivateKeyDer<'static>> {
+    PemReader::new(rd).private_keys()
+}
+
+/// Return an iterator over certificate revocation lists (CRLs) from `rd`, taking ownership
+/// of it.
+///
+/// As for [`certs_owned()`], but for the CRLs of [`crls()`].
+#[cfg(feature = "std")]
+pub fn crls_owned<R: io::BufRead>(rd: R) -> KindIter<R, CertificateRevocationListDer<'static>> {
+    PemReader::new(rd).crls()
+}
+
+/// Return an iterator over certificate signing requests (CSRs) from `rd`, taking ownership
+/// of it.
+///
+/// As for [`certs_owned()`], but for the CSRs of [`csrs()`].
+#[cfg(feature = "std")]
+pub fn csrs_owned<R: io::BufRead>(rd: R) -> KindIter<R, CertificateSigningRequestDer<'static>> {
+    PemReader::new(rd).csrs()
+}
+
+/// Return an iterator over RSA private keys from `rd`, taking ownership of it.
+///
+/// As for [`certs_owned()`], but for the keys of [`rsa_private_keys()`].
+#[cfg(feature = "std")]
+pub fn rsa_private_keys_owned<R: io::BufRead>(rd: R) -> KindIter<R, PrivatePkcs1KeyDer<'static>> {
+    PemReader::new(rd).rsa_private_keys()
+}
+
+/// Return an iterator over PKCS8-encoded private keys from `rd`, taking ownership of it.
+///
+/// As for [`certs_owned()`], but for the keys of [`pkcs8_private_keys()`].
+#[cfg(feature = "std")]
+pub fn pkcs8_private_keys_owned<R: io::BufRead>(rd: R) -> KindIter<R, PrivatePkcs8KeyDer<'static>> {
+    PemReader::new(rd).pkcs8_private_keys()
+}
+
+/// Return an iterator over SEC1-encoded EC private keys from `rd`, taking ownership of it.
+///
+/// As for [`certs_owned()`], but for the keys of [`ec_private_keys()`].
+#[cfg(feature = "std")]
+pub fn ec_private_keys_owned<R: io::BufRead>(rd: R) -> KindIter<R, PrivateSec1KeyDer<'static>> {
+    PemReader::new(rd).ec_private_keys()
+}
+
+/// Return an iterator over SPKI-encoded keys from `rd`, taking ownership of it.
+///
+/// As for [`certs_owned()`], but for the keys of [`public_keys()`].
+#[cfg(feature = "std")]
+pub fn public_keys_owned<R: io::BufRead>(rd: R) -> KindIter<R, SubjectPublicKeyInfoDer<'static>> {
+    PemReader::new(rd).public_keys()
 }
@@ -0,0 +1,43 @@
+use std::fs::File;
+use std::path::Path;
+use std::vec::Vec;
+
+use memmap2::Mmap;
+
+use crate::pemfile::{Diagnostics, Item, PemSection, Position, ReadError};
+use crate::reader::ParserOptions;
+
+/// Extract and decode every PEM section in the file at `path`, by memory-mapping it.
+///
+/// This avoids copying the file through an [`io::BufRead`][std::io::BufRead], which makes
+/// loading large bundles, such as a system's trusted roots, cheaper.  Errors are as for
+/// [`read_one_from_slice()`][crate::read_one_from_slice()], with positions relative to the
+/// start of the file.
+///
+/// Use [`read_all()`][crate::read_all()] for files that other processes may be writing.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated, by this or any other process, until this
+/// returns.  As with any memory map, doing so is undefined behaviour: it may give
+/// inconsistent results or crash the process.
+#[allow(unsafe_code)]
+pub unsafe fn from_path_mmap(path: impl AsRef<Path>) -> Result<Vec<Item>, ReadError> {
+    let file = File::open(path)?;
+
+    // SAFETY: the map is only read, and only for the duration of this call, during which
+    // the caller guarantees that the file is not changed
+    let map = unsafe { Mmap::map(&file)? };
+
+    let options = ParserOptions::default();
+    let (mut position, mut diagnostics) = (Position::default(), Diagnostics::default());
+    let (mut input, mut items) = (&map[..], Vec::new());
+    while let Some((section, rest)) =
+        PemSection::from_slice(input, &mut position, &options, &mut diagnostics)?
+    {
+        items.push(section.item);
+        input = rest;
+    }
+
+    Ok(items)
+}
@@ -0,0 +1,212 @@
+use core::ops::Range;
+use std::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::base64::{self, Decoder};
+use crate::pemfile::{Diagnostics, Error, Item, PemSection, Position, Totals};
+use crate::reader::ParserOptions;
+
+/// Extract and decode every PEM section in `input`, decoding them in parallel.
+///
+/// The sections are decoded across the [rayon] global thread pool, which makes loading
+/// large bundles, such as a system's trusted roots, faster.  The items are in the order of
+/// their sections, and the result is otherwise as for calling
+/// [`read_one_from_slice()`][crate::read_one_from_slice()] until the input is exhausted:
+/// so reading stops at the first error, whose positions are relative to the start of
+/// `input`.
+pub fn read_all_parallel(input: &[u8]) -> Result<Vec<Item>, Error> {
+    read_all(input, &ParserOptions::default())
+}
+
+/// Read every section in `input`, decoding them across the rayon thread pool.
+///
+/// The input is first split after each END line, which is cheap, and the pieces are then
+/// parsed in parallel.  If a piece cannot be parsed, or would take the input over the limits
+/// on the whole of it, the input is parsed in order from the start of that piece instead;
+/// so the items, and any error, are as for parsing all of the input in order.
+pub(crate) fn read_all(input: &[u8], options: &ParserOptions) -> Result<Vec<Item>, Error> {
+    let options = &options.clone().skip_malformed(false);
+    let pieces = split(input);
+    let parsed = pieces
+        .par_iter()
+        .map(|(range, start)| {
+            let mut totals = Totals::default();
+            let piece = read_piece(&input[range.clone()], *start, options, &mut totals);
+            piece.ok().map(|piece| (piece, totals))
+        })
+        .collect::<Vec<_>>();
+
+    let mut items = Vec::new();
+    let mut totals = Totals::default();
+    for (parsed, (range, start)) in parsed.into_iter().zip(&pieces) {
+        match parsed {
+            Some((piece, counted)) if totals.add(counted, options) => items.extend(piece),
+            _ => {
+                let rest = &input[range.start..];
+                items.extend(read_piece(rest, *start, options, &mut totals)?);
+                break;
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+/// Read every section in `piece`, which starts at `position` in the input, adding them to
+/// `totals`.
+fn read_piece(
+    mut piece: &[u8],
+    mut position: Position,
+    options: &ParserOptions,
+    totals: &mut Totals,
+) -> Result<Vec<Item>, Error> {
+    let mut diagnostics = Diagnostics::default();
+    let mut items = Vec::new();
+    while let Some((section, rest)) = PemSection::from_slice_counted(
+        piece,
+        &mut position,
+        options,
+        &mut diagnostics,
+        Some(totals),
+    )? {
+        items.push(section.item);
+        piece = rest;
+    }
+
+    Ok(items)
+}
+
+/// Split `input` after each line that starts with `-----END `, giving each piece with
+/// where it starts.
+fn split(input: &[u8]) -> Vec<(Range<usize>, Position)> {
+    const END: &[u8] = b"-----END ";
+
+    let mut pieces = Vec::new();
+    let mut start = Position::default();
+    let (mut offset, mut line) = (0, 1);
+    for text in input.split_inclusive(|&b| b == b'\n') {
+        offset += text.len();
+        line += 1;
+        let end = text.len() >= END.len() && text[..END.len()].eq_ignore_ascii_case(END);
+        if end {
+            pieces.push((start.offset..offset, start));
+            start = Position { offset, line };
+        }
+    }
+
+    if start.offset < input.len() {
+        pieces.push((start.offset..input.len(), start));
+    }
+    pieces
+}
+
+/// The body of a section, held until its END line so that it can be decoded in parallel.
+///
+/// See [`ParserOptions::decode_in_parallel()`].
+#[derive(Default)]
+pub(crate) struct Body {
+    text: Vec<u8>,
+    /// Where each line ends in `text`, and where it starts in the input.
+    lines: Vec<(usize, Position)>,
+}
+
+impl Body {
+    pub(crate) fn push(&mut self, line: &[u8], position: Position) {
+        self.text.extend_from_slice(line);
+        self.lines.push((self.text.len(), position));
+    }
+
+    /// Decode the body with `decoder`, which must be fresh, appending the result to
+    /// `output`; `decoder` is left to decode the final block.
+    ///
+    /// Bodies of at least [`Body::PARALLEL_MIN`] bytes are split, between lines, into
+    /// chunks of whole quads; all but the last chunk are decoded across the rayon thread
+    /// pool, and the last with `decoder`.  If any chunk cannot be decoded, perhaps because
+    /// it does not end between quads, the body is decoded in order from the start
+    /// instead: so any error, and its position, is as for decoding the lines as they were
+    /// read.
+    pub(crate) fn decode(
+        self,
+        decoder: &mut Decoder,
+        output: &mut Vec<u8>,
+    ) -> Result<(), (base64::Error, Position)> {
+        let mut first = 0;
+        let chunks = self.chunks();
+        if let Some((last, init)) = chunks.split_last() {
+            let secret = decoder.is_secret();
+            let decoded = init
+                .par_iter()
+                .map(|chunk| {
+                    let mut decoder = Decoder::new(secret);
+                    let mut output = Vec::new();
+                    for index in chunk.clone() {
+                        decoder.update(self.line(index), &mut output).ok()?;
+                    }
+                    match decoder.between_quads() {
+                        true => decoder.finish(&mut output).ok().map(|_| output),
+                        false => None,
+                    }
+                })
+                .collect::<Option<Vec<_>>>();
+
+            if let Some(decoded) = decoded {
+                output.reserve(decoded.iter().map(Vec::len).sum());
+                decoded
+                    .iter()
+                    .for_each(|chunk| output.extend_from_slice(chunk));
+                first = last.start;
+            }
+        }
+
+        for index in first..self.lines.len() {
+            let position = self.lines[index].1;
+            decoder
+                .update(self.line(index), output)
+                .map_err(|(error, offset)| {
+                    let offset = position.offset + offset;
+                    (error, Position { offset, ..position })
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Group the lines into chunks of about [`Body::CHUNK`] bytes, each ending where the
+    /// length of the text so far is a whole number of quads.
+    ///
+    /// This is empty if the body is too small to be worth decoding in parallel.
+    fn chunks(&self) -> Vec<Range<usize>> {
+        let mut chunks = Vec::new();
+        if self.text.len() < Self::PARALLEL_MIN {
+            return chunks;
+        }
+
+        let (mut start, mut symbols, mut chunk_start) = (0, 0, 0);
+        for (index, &(end, _)) in self.lines.iter().enumerate() {
+            symbols += self.line(index).trim_ascii().len();
+            if end - chunk_start >= Self::CHUNK && symbols.is_multiple_of(4) {
+                chunks.push(start..index + 1);
+                start = index + 1;
+                chunk_start = end;
+            }
+        }
+
+        chunks.push(start..self.lines.len());
+        chunks
+    }
+
+    fn line(&self, index: usize) -> &[u8] {
+        let start = match index {
+            0 => 0,
+            _ => self.lines[index - 1].0,
+        };
+        &self.text[start..self.lines[index].0]
+    }
+
+    /// The size of body below which it is decoded in order.
+    const PARALLEL_MIN: usize = 1 << 20;
+
+    /// The size of the chunks a body is decoded in.
+    const CHUNK: usize = 1 << 18;
+}
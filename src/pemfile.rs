@@ -1,23 +1,37 @@
-use alloc::format;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::vec::Vec;
-#[cfg(feature = "std")]
-use core::iter;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::ControlFlow;
 #[cfg(feature = "std")]
 use std::io::{self, ErrorKind};
 
 use pki_types::{
-    pem, CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer,
+    CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, Der,
     PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
 };
 
+use crate::base64;
+#[cfg(feature = "parallel")]
+use crate::parallel::Body;
+#[cfg(feature = "std")]
+use crate::reader::PemReader;
+use crate::reader::{ParserOptions, Redaction, SliceReader};
+use crate::trust::{self, CertificateTrust};
+#[cfg(feature = "std")]
+use crate::utf16::{Utf16, Utf16Reader};
+use crate::warning::Warning;
+
 /// The contents of a single recognised block in a PEM file.
 #[non_exhaustive]
 #[derive(Debug, PartialEq)]
 pub enum Item {
     /// A DER-encoded x509 certificate.
     ///
-    /// Appears as "CERTIFICATE" in PEM files.
+    /// Appears as "CERTIFICATE" in PEM files, or as "X509 CERTIFICATE" or "X.509 CERTIFICATE"
+    /// in those produced by some legacy tooling.
     X509Certificate(CertificateDer<'static>),
 
     /// A DER-encoded Subject Public Key Info; as specified in RFC 7468.
@@ -47,44 +61,495 @@ pub enum Item {
 
     /// A Certificate Signing Request; as specified in RFC 2986
     ///
-    /// Appears as "CERTIFICATE REQUEST" in PEM files.
+    /// Appears as "CERTIFICATE REQUEST" in PEM files, or as "NEW CERTIFICATE REQUEST" in
+    /// those produced by Java's keytool and older Microsoft tooling.
     Csr(CertificateSigningRequestDer<'static>),
+
+    /// A DER-encoded plaintext DSA private key; as used by OpenSSL.
+    ///
+    /// This crate does not otherwise support DSA keys: this variant exists so that
+    /// callers can report their presence.
+    ///
+    /// Appears as "DSA PRIVATE KEY" in PEM files.
+    DsaKey(Der<'static>),
+
+    /// A DER-encoded RSA public key; as specified in PKCS #1/RFC 3447
+    ///
+    /// Appears as "RSA PUBLIC KEY" in PEM files.
+    Pkcs1PublicKey(Der<'static>),
+
+    /// DER-encoded Diffie-Hellman parameters; as specified in PKCS #3
+    ///
+    /// Appears as "DH PARAMETERS" in PEM files.
+    DhParameters(Der<'static>),
+
+    /// DER-encoded elliptic curve domain parameters; as specified in RFC 3279
+    ///
+    /// Appears as "EC PARAMETERS" in PEM files, typically preceding an "EC PRIVATE KEY".
+    EcParameters(Der<'static>),
+
+    /// DER-encoded Diffie-Hellman domain parameters; as specified in ANSI X9.42/RFC 3279
+    ///
+    /// Appears as "X9.42 DH PARAMETERS" in PEM files.
+    X942DhParameters(Der<'static>),
+
+    /// DER-encoded DSA domain parameters; as specified in RFC 3279
+    ///
+    /// Appears as "DSA PARAMETERS" in PEM files.
+    DsaParameters(Der<'static>),
+
+    /// A DER-encoded attribute certificate; as specified in RFC 5755
+    ///
+    /// Appears as "ATTRIBUTE CERTIFICATE" in PEM files.
+    AttributeCertificate(Der<'static>),
+
+    /// A DER-encoded cross-certificate pair; as specified in X.509
+    ///
+    /// Appears as "CERTIFICATE PAIR" in PEM files.
+    CertificatePair(Der<'static>),
+
+    /// A DER-encoded `TrustAnchorInfo`; as specified in RFC 5914
+    ///
+    /// Appears as "TRUST ANCHOR" in PEM files.
+    TrustAnchor(Der<'static>),
+
+    /// A private key in the OpenSSH-specific binary format; as produced by `ssh-keygen`
+    ///
+    /// This is not a DER encoding, nor is it interchangeable with PKCS #8; this variant exists
+    /// so that callers can recognise such keys. `ssh-keygen -p -m pkcs8` converts them.
+    ///
+    /// Appears as "OPENSSH PRIVATE KEY" in PEM files.
+    OpenSshKey(Vec<u8>),
+
+    /// An SSH public key blob, with any headers; as specified in RFC 4716
+    ///
+    /// Appears between "---- BEGIN SSH2 PUBLIC KEY ----" and "---- END SSH2 PUBLIC KEY ----"
+    /// markers (note: four dashes and a space) in files exported by SSH implementations.
+    Ssh2PublicKey {
+        /// The decoded public key blob, in the SSH wire format of RFC 4253.
+        key: Vec<u8>,
+        /// The `Header-tag: value` pairs that preceded the key blob, in order.
+        ///
+        /// Continuation lines are joined onto the value they continue.
+        headers: Vec<(String, String)>,
+    },
+
+    /// An OpenPGP transferable public key, with any armor headers; as specified in RFC 4880
+    ///
+    /// Appears as "PGP PUBLIC KEY BLOCK" in ASCII-armored files.
+    PgpPublicKey {
+        /// The decoded OpenPGP packets.
+        data: Vec<u8>,
+        /// The armor headers (such as `Version` or `Comment`), in order.
+        headers: Vec<(String, String)>,
+    },
+
+    /// An OpenPGP transferable secret key, with any armor headers; as specified in RFC 4880
+    ///
+    /// Appears as "PGP PRIVATE KEY BLOCK" in ASCII-armored files.
+    PgpPrivateKey {
+        /// The decoded OpenPGP packets.
+        data: Vec<u8>,
+        /// The armor headers (such as `Version` or `Comment`), in order.
+        headers: Vec<(String, String)>,
+    },
+
+    /// An OpenPGP detached signature, with any armor headers; as specified in RFC 4880
+    ///
+    /// Appears as "PGP SIGNATURE" in ASCII-armored files.
+    PgpSignature {
+        /// The decoded OpenPGP packets.
+        data: Vec<u8>,
+        /// The armor headers (such as `Version` or `Comment`), in order.
+        headers: Vec<(String, String)>,
+    },
+
+    /// An OpenPGP message, with any armor headers; as specified in RFC 4880
+    ///
+    /// Appears as "PGP MESSAGE" in ASCII-armored files.
+    PgpMessage {
+        /// The decoded OpenPGP packets.
+        data: Vec<u8>,
+        /// The armor headers (such as `Version` or `Comment`), in order.
+        headers: Vec<(String, String)>,
+    },
+
+    /// A DER-encoded OCSP response; as specified in RFC 6960
+    ///
+    /// Appears as "OCSP RESPONSE" in PEM files.
+    OcspResponse(Der<'static>),
+
+    /// A DER-encoded x509 certificate, with OpenSSL's auxiliary trust settings
+    ///
+    /// Appears as "TRUSTED CERTIFICATE" in PEM files written by `openssl x509 -trustout`.
+    TrustedCertificate {
+        /// The certificate itself.
+        cert: CertificateDer<'static>,
+        /// The trust settings appended to the certificate.
+        trust: CertificateTrust,
+    },
+
+    /// A DER-encoded PKCS #7/CMS `ContentInfo`; as specified in RFC 5652
+    ///
+    /// These are typically certificate bundles ("p7b" files).
+    ///
+    /// Appears as "PKCS7" or "CMS" in PEM files, or as "PKCS #7 SIGNED DATA" in those exported
+    /// from Windows.
+    Pkcs7(Der<'static>),
+
+    /// A section whose contents are encrypted, according to its RFC 1421 headers.
+    ///
+    /// These are the legacy encrypted private keys written by OpenSSL, such as an "RSA
+    /// PRIVATE KEY" section with `Proc-Type: 4,ENCRYPTED` and `DEK-Info` headers.  The
+    /// contents are the ciphertext, so are not returned as the plaintext kind of item the
+    /// label names; `openssl pkey` decrypts them.
+    #[non_exhaustive]
+    LegacyEncrypted {
+        /// The label from the section's BEGIN line, such as `RSA PRIVATE KEY`.
+        label: String,
+        /// The decoded, still encrypted, contents of the section.
+        data: Vec<u8>,
+        /// The section's headers, in order.
+        headers: Vec<(String, String)>,
+    },
+
+    /// A section with a label this crate does not otherwise recognise.
+    ///
+    /// The contents are decoded from base64, but are otherwise uninterpreted.  These are
+    /// only read with [`ParserOptions::yield_unknown()`][crate::ParserOptions::yield_unknown()];
+    /// by default such sections are skipped.
+    #[non_exhaustive]
+    Unknown {
+        /// The label from the section's BEGIN line, such as `BREAKFAST CLUB`.
+        label: Vec<u8>,
+        /// The decoded contents of the section.
+        der: Vec<u8>,
+    },
 }
 
-impl Item {
+/// A PEM section, with what was read around its contents.
+///
+/// This is returned by [`PemReader::read_section()`] and
+/// [`ParserOptions::read_section_from_slice()`], for callers that need more than the
+/// [`Item`] itself.
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+pub struct PemSection {
+    /// The contents of the section.
+    pub item: Item,
+
+    /// The section's headers, in order.
+    ///
+    /// These are RFC 1421 encapsulated headers (such as `Proc-Type` and `DEK-Info` in
+    /// legacy encrypted keys), RFC 4716 headers or OpenPGP armor headers.  Headers are
+    /// not part of the contents, so are skipped when decoding it.
+    pub headers: Vec<(String, String)>,
+
+    /// The text between the previous section (or the start of the input) and this one,
+    /// with surrounding whitespace removed.
+    ///
+    /// This is empty unless [`ParserOptions::capture_preceding_text()`] is set.
+    pub preceding_text: String,
+
+    /// Where the section is in the input.
+    pub span: Span,
+}
+
+impl PemSection {
     #[cfg(feature = "std")]
-    fn from_buf(rd: &mut dyn io::BufRead) -> Result<Option<Self>, pem::Error> {
+    pub(crate) fn from_buf<R: io::BufRead + ?Sized>(
+        rd: &mut R,
+        options: &ParserOptions,
+        state: &mut ReadState,
+    ) -> Result<Option<Self>, io::Error> {
         loop {
-            match pem::from_buf(rd)? {
-                Some((kind, data)) => match Self::from_kind(kind, data) {
-                    Some(item) => return Ok(Some(item)),
-                    None => continue,
-                },
-
+            let section = match from_buf(rd, options, state)? {
+                Some(section) => state.section(section, options)?,
                 None => return Ok(None),
+            };
+
+            state.diagnostics.section(&section, options);
+            match Self::from_section(section, options) {
+                Ok(Some(section)) => return Ok(Some(section)),
+                Ok(None) => continue,
+                Err(err) if options.skip_malformed => state.diagnostics.errors.push(err),
+                Err(err) => return Err(err.into()),
             }
         }
     }
 
-    fn from_slice(pem: &[u8]) -> Result<Option<(Self, &[u8])>, pem::Error> {
-        let mut iter = <(pem::SectionKind, Vec<u8>) as pem::PemObject>::pem_slice_iter(pem);
+    /// As for `from_buf()`, but reading from a slice that starts at `position`, and
+    /// adding to `diagnostics`.
+    ///
+    /// On success, `position` is advanced to the start of the returned remainder of `pem`.
+    pub(crate) fn from_slice<'a>(
+        pem: &'a [u8],
+        position: &mut Position,
+        options: &ParserOptions,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<(Self, &'a [u8])>, Error> {
+        Self::from_slice_counted(pem, position, options, diagnostics, None)
+    }
 
-        for found in iter.by_ref() {
-            match found {
-                Ok((kind, data)) => match Self::from_kind(kind, data) {
-                    Some(item) => return Ok(Some((item, iter.remainder()))),
-                    None => continue,
-                },
+    /// As for `from_slice()`, but also adding the sections read to `totals`, if given, and
+    /// checking them against the limits on the whole input.
+    pub(crate) fn from_slice_counted<'a>(
+        mut pem: &'a [u8],
+        position: &mut Position,
+        options: &ParserOptions,
+        diagnostics: &mut Diagnostics,
+        mut totals: Option<&mut Totals>,
+    ) -> Result<Option<(Self, &'a [u8])>, Error> {
+        loop {
+            let output = &mut Vec::new();
+            let (section, rest) = match from_slice(pem, position, options, diagnostics, output)? {
+                Some((section, rest)) => (section, rest),
+                None => return Ok(None),
+            };
+
+            if let Some(totals) = totals.as_deref_mut() {
+                totals.section(&section, options)?;
+            }
+            diagnostics.section(&section, options);
+            match Self::from_section(section, options) {
+                Ok(Some(section)) => return Ok(Some((section, rest))),
+                Ok(None) => {}
+                Err(err) if options.skip_malformed => diagnostics.errors.push(err),
                 Err(err) => return Err(err),
             }
+            pem = rest;
+        }
+    }
+
+    fn from_section(mut section: Section, options: &ParserOptions) -> Result<Option<Self>, Error> {
+        if let Some(err) = section.error.take() {
+            return Err(err);
+        }
+
+        let headers = section.headers.clone();
+        let preceding_text = String::from_utf8_lossy(&section.preceding)
+            .trim()
+            .to_owned();
+        let span = Span {
+            start: section.position,
+            end: section.end,
+        };
+        Ok(Item::from_section(section, options)?.map(|item| Self {
+            item,
+            headers,
+            preceding_text,
+            span,
+        }))
+    }
+}
+
+/// An item decoded into a buffer provided by the caller, borrowing its contents from it.
+///
+/// This is returned by [`read_all_borrowed()`].  The most common kinds of item borrow their
+/// contents, so that reading a large bundle does not allocate for each section; any other
+/// item is returned as [`BorrowedItem::Owned`].
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+pub enum BorrowedItem<'a> {
+    /// A DER-encoded x509 certificate: see [`Item::X509Certificate`].
+    X509Certificate(CertificateDer<'a>),
+
+    /// A DER-encoded Subject Public Key Info: see [`Item::SubjectPublicKeyInfo`].
+    SubjectPublicKeyInfo(SubjectPublicKeyInfoDer<'a>),
+
+    /// A DER-encoded plaintext RSA private key: see [`Item::Pkcs1Key`].
+    Pkcs1Key(PrivatePkcs1KeyDer<'a>),
+
+    /// A DER-encoded plaintext private key: see [`Item::Pkcs8Key`].
+    Pkcs8Key(PrivatePkcs8KeyDer<'a>),
+
+    /// A Sec1-encoded plaintext private key: see [`Item::Sec1Key`].
+    Sec1Key(PrivateSec1KeyDer<'a>),
+
+    /// A Certificate Revocation List: see [`Item::Crl`].
+    Crl(CertificateRevocationListDer<'a>),
+
+    /// A Certificate Signing Request: see [`Item::Csr`].
+    Csr(CertificateSigningRequestDer<'a>),
+
+    /// Any other item, which owns its contents.
+    Owned(Item),
+}
+
+impl<'a> BorrowedItem<'a> {
+    /// Read every section in `input`, decoding their contents into `buf`.
+    ///
+    /// `buf` is cleared first.  If there is an error, its contents are unspecified.
+    pub(crate) fn from_slice_into(
+        mut input: &[u8],
+        options: &ParserOptions,
+        buf: &'a mut Vec<u8>,
+    ) -> Result<Vec<Self>, Error> {
+        enum Decoded {
+            Borrowed(fn(&[u8]) -> BorrowedItem<'_>, core::ops::Range<usize>),
+            Owned(Item),
+        }
+
+        // a failed section would take the buffer with it
+        let options = &options.clone().skip_malformed(false);
+        let (mut position, mut diagnostics) = (Position::default(), Diagnostics::default());
+        let mut totals = Totals::default();
+        let mut output = core::mem::take(buf);
+        output.clear();
+
+        let mut decoded = Vec::new();
+        while let Some((mut section, rest)) =
+            from_slice(input, &mut position, options, &mut diagnostics, &mut output)?
+        {
+            totals.section(&section, options)?;
+            input = rest;
+            output = core::mem::take(&mut section.data);
+            // encrypted sections are never borrowed as the plaintext item their label names
+            let kind = match is_encrypted(&section.headers) {
+                true => None,
+                false => section.kind,
+            };
+            let borrowed: fn(&[u8]) -> BorrowedItem<'_> = match kind {
+                Some(SectionKind::Certificate) => |der| BorrowedItem::X509Certificate(der.into()),
+                Some(SectionKind::PublicKey) => {
+                    |der| BorrowedItem::SubjectPublicKeyInfo(der.into())
+                }
+                Some(SectionKind::RsaPrivateKey) => |der| BorrowedItem::Pkcs1Key(der.into()),
+                Some(SectionKind::PrivateKey) => |der| BorrowedItem::Pkcs8Key(der.into()),
+                Some(SectionKind::EcPrivateKey) => |der| BorrowedItem::Sec1Key(der.into()),
+                Some(SectionKind::Crl) => |der| BorrowedItem::Crl(der.into()),
+                Some(SectionKind::Csr) => |der| BorrowedItem::Csr(der.into()),
+                _ => {
+                    section.data = output.split_off(section.start);
+                    section.start = 0;
+                    if let Some(item) = Item::from_section(section, options)? {
+                        decoded.push(Decoded::Owned(item));
+                    }
+                    continue;
+                }
+            };
+            decoded.push(Decoded::Borrowed(borrowed, section.start..output.len()));
+        }
+
+        *buf = output;
+        let buf: &'a Vec<u8> = buf;
+        Ok(decoded
+            .into_iter()
+            .map(|decoded| match decoded {
+                Decoded::Borrowed(borrowed, range) => borrowed(&buf[range]),
+                Decoded::Owned(item) => Self::Owned(item),
+            })
+            .collect())
+    }
+
+    /// Copy the contents of this item, if they are borrowed, to make an [`Item`].
+    pub fn into_owned(self) -> Item {
+        match self {
+            Self::X509Certificate(cert) => Item::X509Certificate(cert.into_owned()),
+            Self::SubjectPublicKeyInfo(key) => Item::SubjectPublicKeyInfo(key.into_owned()),
+            Self::Pkcs1Key(key) => Item::Pkcs1Key(key.secret_pkcs1_der().to_vec().into()),
+            Self::Pkcs8Key(key) => Item::Pkcs8Key(key.secret_pkcs8_der().to_vec().into()),
+            Self::Sec1Key(key) => Item::Sec1Key(key.secret_sec1_der().to_vec().into()),
+            Self::Crl(crl) => Item::Crl(crl.as_ref().to_vec().into()),
+            Self::Csr(csr) => Item::Csr(csr.as_ref().to_vec().into()),
+            Self::Owned(item) => item,
+        }
+    }
+}
+
+/// Parse exactly one section, such as a certificate or key embedded as a string in a
+/// configuration file.
+///
+/// Whitespace may surround the section, but no other text: there is an error if there is
+/// no section, or more than one, or any other text.  A section with a label that is not
+/// recognised is parsed as an [`Item::Unknown`].
+///
+/// ```
+/// use rustls_pemfile::Item;
+///
+/// let pem = "-----BEGIN CERTIFICATE-----\nMA==\n-----END CERTIFICATE-----\n";
+/// assert!(matches!(pem.parse::<Item>(), Ok(Item::X509Certificate(_))));
+/// assert!(pem.repeat(2).parse::<Item>().is_err());
+/// ```
+impl core::str::FromStr for Item {
+    type Err = Error;
+
+    fn from_str(pem: &str) -> Result<Self, Error> {
+        let options = ParserOptions::default()
+            .reject_text_outside_sections(true)
+            .yield_unknown(true);
+        let (mut position, mut diagnostics) = (Position::default(), Diagnostics::default());
+        let read =
+            PemSection::from_slice(pem.as_bytes(), &mut position, &options, &mut diagnostics);
+        let (section, rest) = match read? {
+            Some((section, rest)) => (section, rest),
+            None => {
+                return Err(Error::MissingSection {
+                    position: Position {
+                        offset: pem.len(),
+                        line: 1 + pem.bytes().filter(|&b| b == b'\n').count(),
+                    },
+                })
+            }
+        };
+
+        match PemSection::from_slice(rest, &mut position, &options, &mut diagnostics)? {
+            Some((other, _)) => Err(Error::UnexpectedSection {
+                position: other.span.start,
+            }),
+            None => Ok(section.item),
         }
+    }
+}
+
+impl Item {
+    #[cfg(feature = "std")]
+    pub(crate) fn from_buf<R: io::BufRead + ?Sized>(
+        rd: &mut R,
+        options: &ParserOptions,
+        state: &mut ReadState,
+    ) -> Result<Option<Self>, io::Error> {
+        Ok(PemSection::from_buf(rd, options, state)?.map(|section| section.item))
+    }
 
-        Ok(None)
+    pub(crate) fn from_slice<'a>(
+        pem: &'a [u8],
+        options: &ParserOptions,
+    ) -> Result<Option<(Self, &'a [u8])>, Error> {
+        let (mut position, mut diagnostics) = (Position::default(), Diagnostics::default());
+        Ok(
+            PemSection::from_slice(pem, &mut position, options, &mut diagnostics)?
+                .map(|(section, rest)| (section.item, rest)),
+        )
     }
 
-    fn from_kind(kind: pem::SectionKind, data: Vec<u8>) -> Option<Self> {
-        use pem::SectionKind::*;
-        match kind {
+    fn from_section(mut section: Section, options: &ParserOptions) -> Result<Option<Self>, Error> {
+        use SectionKind::*;
+        section.data.drain(..section.start);
+        let Section {
+            label,
+            kind,
+            data,
+            headers,
+            position,
+            ..
+        } = section;
+
+        let kind = match kind {
+            Some(kind) => kind,
+            None if options.yield_unknown => return Ok(Some(Self::Unknown { label, der: data })),
+            None => return Ok(None),
+        };
+
+        if is_encrypted(&headers) {
+            return Ok(Some(Self::LegacyEncrypted {
+                label: String::from_utf8_lossy(&label).into_owned(),
+                data,
+                headers,
+            }));
+        }
+
+        Ok(match kind {
             Certificate => Some(Self::X509Certificate(data.into())),
             PublicKey => Some(Self::SubjectPublicKeyInfo(data.into())),
             RsaPrivateKey => Some(Self::Pkcs1Key(data.into())),
@@ -92,100 +557,2240 @@ impl Item {
             EcPrivateKey => Some(Self::Sec1Key(data.into())),
             Crl => Some(Self::Crl(data.into())),
             Csr => Some(Self::Csr(data.into())),
+            DsaPrivateKey => Some(Self::DsaKey(data.into())),
+            RsaPublicKey => Some(Self::Pkcs1PublicKey(data.into())),
+            DhParameters => Some(Self::DhParameters(data.into())),
+            EcParameters => Some(Self::EcParameters(data.into())),
+            X942DhParameters => Some(Self::X942DhParameters(data.into())),
+            DsaParameters => Some(Self::DsaParameters(data.into())),
+            AttributeCertificate => Some(Self::AttributeCertificate(data.into())),
+            CertificatePair => Some(Self::CertificatePair(data.into())),
+            TrustAnchor => Some(Self::TrustAnchor(data.into())),
+            OpenSshPrivateKey => Some(Self::OpenSshKey(data)),
+            Ssh2PublicKey => Some(Self::Ssh2PublicKey { key: data, headers }),
+            PgpPublicKeyBlock => Some(Self::PgpPublicKey { data, headers }),
+            PgpPrivateKeyBlock => Some(Self::PgpPrivateKey { data, headers }),
+            PgpSignature => Some(Self::PgpSignature { data, headers }),
+            PgpMessage => Some(Self::PgpMessage { data, headers }),
+            OcspResponse => Some(Self::OcspResponse(data.into())),
+            TrustedCertificate => match trust::split(&data) {
+                Some((cert, trust)) => Some(Self::TrustedCertificate { cert, trust }),
+                None => {
+                    return Err(Error::InvalidTrustedCertificate {
+                        label: String::from_utf8_lossy(&label).into_owned(),
+                        position,
+                    })
+                }
+            },
+            Pkcs7 => Some(Self::Pkcs7(data.into())),
+        })
+    }
+
+    /// The label of this item's section, if it is a private key.
+    #[cfg(feature = "std")]
+    pub(crate) fn private_key_label(&self) -> Option<&'static str> {
+        match self {
+            Self::Pkcs1Key(_) => Some("RSA PRIVATE KEY"),
+            Self::Pkcs8Key(_) => Some("PRIVATE KEY"),
+            Self::Sec1Key(_) => Some("EC PRIVATE KEY"),
+            Self::DsaKey(_) => Some("DSA PRIVATE KEY"),
+            Self::OpenSshKey(_) => Some("OPENSSH PRIVATE KEY"),
+            Self::PgpPrivateKey { .. } => Some("PGP PRIVATE KEY BLOCK"),
+            Self::LegacyEncrypted { label, .. } if label.ends_with("PRIVATE KEY") => {
+                Some("encrypted private key")
+            }
             _ => None,
         }
     }
+
+    /// Whether this item holds secret key material.
+    ///
+    /// Unrecognised sections are assumed to, as they are when decoded.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn is_secret(&self) -> bool {
+        match self {
+            Self::Pkcs1Key(_)
+            | Self::Pkcs8Key(_)
+            | Self::Sec1Key(_)
+            | Self::DsaKey(_)
+            | Self::OpenSshKey(_)
+            | Self::PgpPrivateKey { .. }
+            | Self::LegacyEncrypted { .. }
+            | Self::Unknown { .. } => true,
+            Self::X509Certificate(_)
+            | Self::SubjectPublicKeyInfo(_)
+            | Self::Crl(_)
+            | Self::Csr(_)
+            | Self::Pkcs1PublicKey(_)
+            | Self::DhParameters(_)
+            | Self::EcParameters(_)
+            | Self::X942DhParameters(_)
+            | Self::DsaParameters(_)
+            | Self::AttributeCertificate(_)
+            | Self::CertificatePair(_)
+            | Self::TrustAnchor(_)
+            | Self::Ssh2PublicKey { .. }
+            | Self::PgpPublicKey { .. }
+            | Self::PgpSignature { .. }
+            | Self::PgpMessage { .. }
+            | Self::OcspResponse(_)
+            | Self::TrustedCertificate { .. }
+            | Self::Pkcs7(_) => false,
+        }
+    }
+
+    /// The label of this item's section, such as `CERTIFICATE`.
+    ///
+    /// This is the label the item is written with by [`write_one()`][crate::write_one()],
+    /// so is the canonical one for items that were read under a legacy label, such as
+    /// `X509 CERTIFICATE`.  The label of an [`Item::Unknown`] is as read, with any bytes
+    /// that are not UTF-8 replaced.
+    pub fn label(&self) -> Cow<'_, str> {
+        Cow::Borrowed(match self {
+            Self::X509Certificate(_) => "CERTIFICATE",
+            Self::SubjectPublicKeyInfo(_) => "PUBLIC KEY",
+            Self::Pkcs1Key(_) => "RSA PRIVATE KEY",
+            Self::Pkcs8Key(_) => "PRIVATE KEY",
+            Self::Sec1Key(_) => "EC PRIVATE KEY",
+            Self::Crl(_) => "X509 CRL",
+            Self::Csr(_) => "CERTIFICATE REQUEST",
+            Self::DsaKey(_) => "DSA PRIVATE KEY",
+            Self::Pkcs1PublicKey(_) => "RSA PUBLIC KEY",
+            Self::DhParameters(_) => "DH PARAMETERS",
+            Self::EcParameters(_) => "EC PARAMETERS",
+            Self::X942DhParameters(_) => "X9.42 DH PARAMETERS",
+            Self::DsaParameters(_) => "DSA PARAMETERS",
+            Self::AttributeCertificate(_) => "ATTRIBUTE CERTIFICATE",
+            Self::CertificatePair(_) => "CERTIFICATE PAIR",
+            Self::TrustAnchor(_) => "TRUST ANCHOR",
+            Self::OpenSshKey(_) => "OPENSSH PRIVATE KEY",
+            Self::Ssh2PublicKey { .. } => "SSH2 PUBLIC KEY",
+            Self::PgpPublicKey { .. } => "PGP PUBLIC KEY BLOCK",
+            Self::PgpPrivateKey { .. } => "PGP PRIVATE KEY BLOCK",
+            Self::PgpSignature { .. } => "PGP SIGNATURE",
+            Self::PgpMessage { .. } => "PGP MESSAGE",
+            Self::OcspResponse(_) => "OCSP RESPONSE",
+            Self::TrustedCertificate { .. } => "TRUSTED CERTIFICATE",
+            Self::Pkcs7(_) => "PKCS7",
+            Self::LegacyEncrypted { label, .. } => label,
+            Self::Unknown { label, .. } => return String::from_utf8_lossy(label),
+        })
+    }
+
+    /// The decoded contents of this item's section.
+    ///
+    /// This is the DER encoding, except for the OpenSSH and OpenPGP items, which are in
+    /// their own binary formats and whose headers are dropped, and for an
+    /// [`Item::LegacyEncrypted`], which is still encrypted.  For an
+    /// [`Item::TrustedCertificate`], it is the certificate followed by its trust settings.
+    pub fn into_der(self) -> Vec<u8> {
+        match self {
+            Self::X509Certificate(cert) => cert.as_ref().to_vec(),
+            Self::SubjectPublicKeyInfo(spki) => spki.as_ref().to_vec(),
+            Self::Pkcs1Key(key) => key.secret_pkcs1_der().to_vec(),
+            Self::Pkcs8Key(key) => key.secret_pkcs8_der().to_vec(),
+            Self::Sec1Key(key) => key.secret_sec1_der().to_vec(),
+            Self::Crl(crl) => crl.as_ref().to_vec(),
+            Self::Csr(csr) => csr.as_ref().to_vec(),
+            Self::DsaKey(der)
+            | Self::Pkcs1PublicKey(der)
+            | Self::DhParameters(der)
+            | Self::EcParameters(der)
+            | Self::X942DhParameters(der)
+            | Self::DsaParameters(der)
+            | Self::AttributeCertificate(der)
+            | Self::CertificatePair(der)
+            | Self::TrustAnchor(der)
+            | Self::OcspResponse(der)
+            | Self::Pkcs7(der) => der.as_ref().to_vec(),
+            Self::OpenSshKey(key) | Self::Ssh2PublicKey { key, .. } => key,
+            Self::PgpPublicKey { data, .. }
+            | Self::PgpPrivateKey { data, .. }
+            | Self::PgpSignature { data, .. }
+            | Self::PgpMessage { data, .. }
+            | Self::LegacyEncrypted { data, .. } => data,
+            Self::TrustedCertificate { cert, trust } => trust::join(&cert, &trust),
+            Self::Unknown { der, .. } => der,
+        }
+    }
+}
+
+/// Whether `headers` are RFC 1421 headers saying the contents are encrypted.
+pub(crate) fn is_encrypted(headers: &[(String, String)]) -> bool {
+    headers
+        .iter()
+        .any(|(tag, value)| tag == "Proc-Type" && value.trim() == "4,ENCRYPTED")
+}
+
+/// The label of a section this crate knows how to decode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SectionKind {
+    Certificate,
+    PublicKey,
+    RsaPrivateKey,
+    PrivateKey,
+    EcPrivateKey,
+    Crl,
+    Csr,
+    DsaPrivateKey,
+    RsaPublicKey,
+    DhParameters,
+    EcParameters,
+    X942DhParameters,
+    DsaParameters,
+    AttributeCertificate,
+    CertificatePair,
+    TrustAnchor,
+    OpenSshPrivateKey,
+    Ssh2PublicKey,
+    PgpPublicKeyBlock,
+    PgpPrivateKeyBlock,
+    PgpSignature,
+    PgpMessage,
+    OcspResponse,
+    TrustedCertificate,
+    Pkcs7,
+}
+
+impl SectionKind {
+    fn secret(&self) -> bool {
+        match self {
+            Self::RsaPrivateKey
+            | Self::PrivateKey
+            | Self::EcPrivateKey
+            | Self::DsaPrivateKey
+            | Self::OpenSshPrivateKey
+            | Self::PgpPrivateKeyBlock => true,
+            Self::Certificate
+            | Self::PublicKey
+            | Self::Crl
+            | Self::Csr
+            | Self::RsaPublicKey
+            | Self::DhParameters
+            | Self::EcParameters
+            | Self::X942DhParameters
+            | Self::DsaParameters
+            | Self::AttributeCertificate
+            | Self::CertificatePair
+            | Self::TrustAnchor
+            | Self::Ssh2PublicKey
+            | Self::PgpPublicKeyBlock
+            | Self::PgpSignature
+            | Self::PgpMessage
+            | Self::OcspResponse
+            | Self::TrustedCertificate
+            | Self::Pkcs7 => false,
+        }
+    }
+}
+
+/// A decoded section, prior to conversion into an [`Item`].
+struct Section {
+    label: Vec<u8>,
+    /// `None` if the label is not recognised.
+    kind: Option<SectionKind>,
+    data: Vec<u8>,
+    /// Where the contents start in `data`, after those of earlier sections decoded into the
+    /// same buffer: see [`Buffers::output`].
+    start: usize,
+    headers: Vec<(String, String)>,
+    /// Any text captured before the section.
+    preceding: Vec<u8>,
+    /// Where the BEGIN line is; or, for a section that could not be read, the error.
+    position: Position,
+    /// Where the END line's marker ends.
+    end: Position,
+    /// Why the section could not be read, when errors are being skipped.
+    error: Option<Error>,
+    /// The line that ended the section belongs to the next, so must be read again.
+    reread_line: bool,
+}
+
+/// The buffers that are carried from one line to the next while reading.
+#[derive(Debug, Default)]
+struct Buffers {
+    /// Any text outside sections being captured.
+    captured: Vec<u8>,
+    /// The buffer the next section's contents are decoded into, after those already in it.
+    ///
+    /// This is normally empty; see [`BorrowedItem`][crate::BorrowedItem].
+    output: Vec<u8>,
+}
+
+/// A section whose BEGIN line has been seen, but not yet its END line.
+struct OpenSection {
+    label: Vec<u8>,
+    encapsulation: Encapsulation,
+    headers: Vec<(String, String)>,
+    /// The previous header line ended with a `\`, so this line continues it.
+    continued: bool,
+    /// The CRC-24 from an OpenPGP armor checksum line, if one was seen.
+    checksum: Option<u32>,
+    /// A base64 line shorter than 64 characters was seen, so it must be the last.
+    short_line: bool,
+    /// A line that is not a header was seen, so no more headers may follow.
+    body: bool,
+    /// Any text captured before the section.
+    preceding: Vec<u8>,
+    /// Where the BEGIN line is.
+    position: Position,
+    /// Decodes the body as it is read.
+    decoder: base64::Decoder,
+    /// The body decoded so far, after `start` bytes of earlier sections.
+    data: Vec<u8>,
+    start: usize,
+    /// The length of the body's lines, including any headers, before decoding.
+    body_len: usize,
+    /// Where the last non-whitespace character of the body is.
+    last: Option<Position>,
+    /// The first error decoding the body.
+    undecodable: Option<Error>,
+    /// The body, when it is decoded in parallel at the END line rather than as it is read.
+    #[cfg(feature = "parallel")]
+    held: Option<Body>,
+    /// The section is not wanted, so its lines are skipped until its END line.
+    skip: bool,
+    /// Why the section could not be read, when errors are being skipped.
+    error: Option<Error>,
+}
+
+/// The flavour of textual encapsulation used by a section.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Encapsulation {
+    /// RFC 7468 PEM: no headers, except RFC 1421 headers before the body.
+    Rfc7468,
+    /// RFC 4716 SSH public keys: headers, which may be continued.
+    Rfc4716,
+    /// RFC 4880 OpenPGP ASCII armor: headers, and a trailing checksum line.
+    OpenPgp,
+}
+
+impl TryFrom<&[u8]> for SectionKind {
+    type Error = ();
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Ok(match value {
+            b"CERTIFICATE" | b"X509 CERTIFICATE" | b"X.509 CERTIFICATE" => Self::Certificate,
+            b"PUBLIC KEY" => Self::PublicKey,
+            b"RSA PRIVATE KEY" => Self::RsaPrivateKey,
+            b"PRIVATE KEY" => Self::PrivateKey,
+            b"EC PRIVATE KEY" => Self::EcPrivateKey,
+            b"X509 CRL" => Self::Crl,
+            b"CERTIFICATE REQUEST" | b"NEW CERTIFICATE REQUEST" => Self::Csr,
+            b"DSA PRIVATE KEY" => Self::DsaPrivateKey,
+            b"RSA PUBLIC KEY" => Self::RsaPublicKey,
+            b"DH PARAMETERS" => Self::DhParameters,
+            b"EC PARAMETERS" => Self::EcParameters,
+            b"X9.42 DH PARAMETERS" => Self::X942DhParameters,
+            b"DSA PARAMETERS" => Self::DsaParameters,
+            b"ATTRIBUTE CERTIFICATE" => Self::AttributeCertificate,
+            b"CERTIFICATE PAIR" => Self::CertificatePair,
+            b"TRUST ANCHOR" => Self::TrustAnchor,
+            b"OPENSSH PRIVATE KEY" => Self::OpenSshPrivateKey,
+            b"SSH2 PUBLIC KEY" => Self::Ssh2PublicKey,
+            b"PGP PUBLIC KEY BLOCK" => Self::PgpPublicKeyBlock,
+            b"PGP PRIVATE KEY BLOCK" => Self::PgpPrivateKeyBlock,
+            b"PGP SIGNATURE" => Self::PgpSignature,
+            b"PGP MESSAGE" => Self::PgpMessage,
+            b"OCSP RESPONSE" => Self::OcspResponse,
+            b"TRUSTED CERTIFICATE" => Self::TrustedCertificate,
+            b"PKCS7" | b"CMS" | b"PKCS #7 SIGNED DATA" => Self::Pkcs7,
+            _ => return Err(()),
+        })
+    }
 }
 
 /// Errors that may arise when parsing the contents of a PEM file
 ///
-/// This differs from [`rustls_pki_types::pem::Error`] because it is `PartialEq`;
-/// it is retained for compatibility.
+/// Each error records where in the input it was found, as a [`Position`].
+#[non_exhaustive]
 #[derive(Debug, PartialEq)]
 pub enum Error {
     /// a section is missing its "END marker" line
     MissingSectionEnd {
         /// the expected "END marker" line that was not found
         end_marker: Vec<u8>,
+        /// the position of the section's "BEGIN marker" line
+        position: Position,
     },
 
     /// syntax error found in the line that starts a new section
     IllegalSectionStart {
-        /// line that contains the syntax error
-        line: Vec<u8>,
+        /// line that contains the syntax error, unless it was redacted: see
+        /// [`ParserOptions::redact()`]
+        line: Option<Vec<u8>>,
+        /// the length of that line, excluding any line ending
+        length: usize,
+        /// the position of that line
+        position: Position,
     },
 
     /// base64 decode error
-    Base64Decode(String),
+    Base64Decode {
+        /// the error, unless it was an invalid character that was redacted: see
+        /// [`ParserOptions::redact()`]
+        error: Option<base64::Error>,
+        /// the label of the section being decoded
+        label: String,
+        /// the position of the character where the error was found
+        position: Position,
+        /// the position of the section's "BEGIN marker" line
+        section: Position,
+    },
+
+    /// a "BEGIN marker" line within a section, in strict mode
+    NestedSectionStart {
+        /// the label of the section it is within
+        label: String,
+        /// the position of the line
+        position: Position,
+        /// the position of the "BEGIN marker" line of the section it is within
+        section: Position,
+    },
+
+    /// text outside sections, in strict mode or when it is rejected
+    TextOutsideSection {
+        /// the position of the first character of the text
+        position: Position,
+    },
+
+    /// whitespace within a line, in strict mode
+    UnexpectedWhitespace {
+        /// the position of the line
+        position: Position,
+        /// the position of the section's "BEGIN marker" line
+        section: Position,
+    },
+
+    /// a base64 line of the wrong length, in strict mode
+    InvalidLineLength {
+        /// the position of the line
+        position: Position,
+        /// the position of the section's "BEGIN marker" line
+        section: Position,
+    },
+
+    /// base64 that is not canonically padded, in strict mode
+    NonCanonicalPadding {
+        /// the label of the section
+        label: String,
+        /// the position of the section's "BEGIN marker" line
+        position: Position,
+    },
+
+    /// an OpenPGP armor checksum that does not match the contents
+    InvalidArmorChecksum {
+        /// the label of the section
+        label: String,
+        /// the position of the section's "BEGIN marker" line
+        position: Position,
+    },
+
+    /// a "TRUSTED CERTIFICATE" section that is not a certificate followed by trust settings
+    InvalidTrustedCertificate {
+        /// the label of the section
+        label: String,
+        /// the position of the section's "BEGIN marker" line
+        position: Position,
+    },
+
+    /// a section exceeding [`ParserOptions::max_section_bytes()`]
+    SectionTooLarge {
+        /// the position of the line that exceeded it
+        position: Position,
+        /// the position of the section's "BEGIN marker" line, unless it was the text
+        /// preceding the section that exceeded it
+        section: Option<Position>,
+    },
+
+    /// a line exceeding [`ParserOptions::max_line_length()`]
+    LineTooLong {
+        /// the position of the line
+        position: Position,
+        /// the position of the "BEGIN marker" line of the section it is within, if any
+        section: Option<Position>,
+    },
+
+    /// a section exceeding [`ParserOptions::max_sections()`]
+    TooManySections {
+        /// the position of the section's "BEGIN marker" line
+        position: Position,
+    },
+
+    /// a section exceeding [`ParserOptions::max_total_decoded()`]
+    DecodedBudgetExceeded {
+        /// the position of the section's "BEGIN marker" line
+        position: Position,
+    },
+
+    /// no section, where exactly one was expected by the `FromStr` implementation
+    /// of [`Item`]
+    MissingSection {
+        /// the position of the end of the input
+        position: Position,
+    },
+
+    /// another section, where exactly one was expected by the `FromStr` implementation
+    /// of [`Item`]
+    UnexpectedSection {
+        /// the position of the other section's "BEGIN marker" line
+        position: Position,
+    },
 }
 
-#[cfg(feature = "std")]
-impl From<Error> for io::Error {
-    fn from(error: Error) -> Self {
-        match error {
-            Error::MissingSectionEnd { end_marker } => io::Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "section end {:?} missing",
-                    String::from_utf8_lossy(&end_marker)
-                ),
-            ),
+impl Error {
+    /// Where in the input the error was found.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::MissingSectionEnd { position, .. }
+            | Self::IllegalSectionStart { position, .. }
+            | Self::Base64Decode { position, .. }
+            | Self::NestedSectionStart { position, .. }
+            | Self::TextOutsideSection { position }
+            | Self::UnexpectedWhitespace { position, .. }
+            | Self::InvalidLineLength { position, .. }
+            | Self::NonCanonicalPadding { position, .. }
+            | Self::InvalidArmorChecksum { position, .. }
+            | Self::InvalidTrustedCertificate { position, .. }
+            | Self::SectionTooLarge { position, .. }
+            | Self::LineTooLong { position, .. }
+            | Self::TooManySections { position }
+            | Self::DecodedBudgetExceeded { position }
+            | Self::MissingSection { position }
+            | Self::UnexpectedSection { position } => *position,
+        }
+    }
 
-            Error::IllegalSectionStart { line } => io::Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "illegal section start: {:?}",
-                    String::from_utf8_lossy(&line)
-                ),
-            ),
+    /// Where in the input the section in which the error was found starts.
+    ///
+    /// Together with [`Error::position()`], this gives the extent of the input to highlight.
+    /// This is `None` for errors found outside sections.
+    pub fn section_start(&self) -> Option<Position> {
+        match self {
+            Self::Base64Decode { section, .. }
+            | Self::NestedSectionStart { section, .. }
+            | Self::UnexpectedWhitespace { section, .. }
+            | Self::InvalidLineLength { section, .. } => Some(*section),
+            Self::SectionTooLarge { section, .. } | Self::LineTooLong { section, .. } => *section,
+            Self::MissingSectionEnd { position, .. }
+            | Self::IllegalSectionStart { position, .. }
+            | Self::NonCanonicalPadding { position, .. }
+            | Self::InvalidArmorChecksum { position, .. }
+            | Self::InvalidTrustedCertificate { position, .. }
+            | Self::TooManySections { position }
+            | Self::DecodedBudgetExceeded { position }
+            | Self::UnexpectedSection { position } => Some(*position),
+            Self::TextOutsideSection { .. } | Self::MissingSection { .. } => None,
+        }
+    }
 
-            Error::Base64Decode(err) => io::Error::new(ErrorKind::InvalidData, err),
+    /// The label of the section in which the error was found, for errors within a section's
+    /// body.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Self::Base64Decode { label, .. }
+            | Self::NestedSectionStart { label, .. }
+            | Self::NonCanonicalPadding { label, .. }
+            | Self::InvalidArmorChecksum { label, .. }
+            | Self::InvalidTrustedCertificate { label, .. } => Some(label),
+            _ => None,
         }
     }
 }
 
-impl From<pem::Error> for Error {
-    fn from(error: pem::Error) -> Self {
-        match error {
-            pem::Error::MissingSectionEnd { end_marker } => Error::MissingSectionEnd { end_marker },
-            pem::Error::IllegalSectionStart { line } => Error::IllegalSectionStart { line },
-            pem::Error::Base64Decode(str) => Error::Base64Decode(str),
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSectionEnd {
+                end_marker,
+                position,
+            } => write!(
+                f,
+                "section end {:?} missing, for the section starting at {}",
+                String::from_utf8_lossy(end_marker),
+                position
+            ),
+            Self::IllegalSectionStart {
+                line: Some(line),
+                position,
+                ..
+            } => write!(
+                f,
+                "illegal section start at {}: {:?}",
+                position,
+                String::from_utf8_lossy(line)
+            ),
+            Self::IllegalSectionStart {
+                line: None,
+                length,
+                position,
+            } => write!(
+                f,
+                "illegal section start at {}: {} bytes, redacted",
+                position, length
+            ),
+            Self::Base64Decode {
+                error,
+                label,
+                position,
+                ..
+            } => {
+                write!(
+                    f,
+                    "base64 decode error at {}, while decoding the {:?} section: ",
+                    position, label
+                )?;
+                match error {
+                    Some(error) => error.fmt(f),
+                    None => f.write_str("invalid character, redacted"),
+                }
+            }
+            Self::NestedSectionStart {
+                label, position, ..
+            } => write!(
+                f,
+                "section start at {} within the {:?} section",
+                position, label
+            ),
+            Self::TextOutsideSection { position } => {
+                write!(f, "text outside sections at {}", position)
+            }
+            Self::UnexpectedWhitespace { position, .. } => {
+                write!(f, "unexpected whitespace at {}", position)
+            }
+            Self::InvalidLineLength { position, .. } => {
+                write!(f, "base64 line of invalid length at {}", position)
+            }
+            Self::NonCanonicalPadding { label, position } => write!(
+                f,
+                "non-canonical base64 padding in the {:?} section starting at {}",
+                label, position
+            ),
+            Self::InvalidArmorChecksum { label, position } => write!(
+                f,
+                "armor checksum mismatch in the {:?} section starting at {}",
+                label, position
+            ),
+            Self::InvalidTrustedCertificate { label, position } => write!(
+                f,
+                "malformed trusted certificate in the {:?} section starting at {}",
+                label, position
+            ),
+            Self::SectionTooLarge { position, .. } => {
+                write!(f, "section too large, at {}", position)
+            }
+            Self::LineTooLong { position, .. } => write!(f, "line too long at {}", position),
+            Self::TooManySections { position } => write!(
+                f,
+                "too many sections, at the section starting at {}",
+                position
+            ),
+            Self::MissingSection { position } => {
+                write!(
+                    f,
+                    "no section found, by the end of the input at {}",
+                    position
+                )
+            }
+            Self::UnexpectedSection { position } => write!(
+                f,
+                "another section at {}, where only one was expected",
+                position
+            ),
+            Self::DecodedBudgetExceeded { position } => write!(
+                f,
+                "decoded size limit exceeded, by the section starting at {}",
+                position
+            ),
+        }
+    }
+}
 
-            // this is a necessary bodge to funnel any new errors into our existing type
-            // (to which we can add no new variants)
-            other => Error::Base64Decode(format!("{other:?}")),
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Base64Decode {
+                error: Some(error), ..
+            } => Some(error),
+            _ => None,
         }
     }
 }
 
-/// Extract and decode the next PEM section from `input`
-///
-/// - `Ok(None)` is returned if there is no PEM section to read from `input`
-/// - Syntax errors and decoding errors produce a `Err(...)`
-/// - Otherwise each decoded section is returned with a `Ok(Some((Item::..., remainder)))` where
-///   `remainder` is the part of the `input` that follows the returned section
-pub fn read_one_from_slice(input: &[u8]) -> Result<Option<(Item, &[u8])>, Error> {
-    Item::from_slice(input).map_err(Into::into)
+/// The `io::Error` wraps `error`, which can be recovered with [`io::Error::get_ref()`] and
+/// downcasting.
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        io::Error::new(ErrorKind::InvalidData, error)
+    }
 }
 
-/// Extract and decode the next PEM section from `rd`.
-///
-/// - Ok(None) is returned if there is no PEM section read from `rd`.
-/// - Underlying IO errors produce a `Err(...)`
-/// - Otherwise each decoded section is returned with a `Ok(Some(Item::...))`
+/// An error from one of the convenience functions, such as [`certs()`](crate::certs).
 ///
-/// You can use this function to build an iterator, for example:
-/// `for item in iter::from_fn(|| read_one(rd).transpose()) { ... }`
+/// Unlike the `io::Error` returned by [`read_one()`], this tells apart a failure of the
+/// underlying reader from a problem with the input, and base64 corruption from other
+/// problems with the input.
 #[cfg(feature = "std")]
-pub fn read_one(rd: &mut dyn io::BufRead) -> Result<Option<Item>, io::Error> {
-    Item::from_buf(rd).map_err(|err| match err {
-        pem::Error::Io(io) => io,
-        other => Error::from(other).into(),
-    })
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ReadError {
+    /// the underlying reader failed
+    Io(io::Error),
+
+    /// the input is not well-formed, or exceeds a limit of the [`ParserOptions`]
+    Syntax(Error),
+
+    /// the body of a section is not valid base64
+    ///
+    /// This is [`Error::Base64Decode`] or [`Error::NonCanonicalPadding`].
+    Base64(Error),
+
+    /// no private key of the kind requested was found, by one of the strict helpers such
+    /// as [`rsa_private_keys_strict()`](crate::rsa_private_keys_strict)
+    KeyNotFound {
+        /// the label of the kind of key requested, such as "RSA PRIVATE KEY"
+        expected: &'static str,
+        /// the labels of the other kinds of private key found, in the order first found
+        found: Vec<&'static str>,
+    },
+
+    /// no item of the type requested was found, by one of the [`FromPem`](crate::FromPem)
+    /// methods that read one
+    NotFound {
+        /// what was requested, such as "certificate"
+        expected: &'static str,
+    },
+
+    /// more than one private key was found, by
+    /// [`identity_strict()`](crate::identity_strict)
+    MultipleKeys {
+        /// the line the first key starts on
+        first: usize,
+        /// the lines the other keys start on
+        extra: Vec<usize>,
+    },
 }
 
-/// Extract and return all PEM sections by reading `rd`.
 #[cfg(feature = "std")]
-pub fn read_all(rd: &mut dyn io::BufRead) -> impl Iterator<Item = Result<Item, io::Error>> + '_ {
-    iter::from_fn(move || read_one(rd).transpose())
+impl ReadError {
+    /// The problem found with the input, if it is malformed.
+    pub fn error(&self) -> Option<&Error> {
+        match self {
+            Self::Io(_)
+            | Self::KeyNotFound { .. }
+            | Self::NotFound { .. }
+            | Self::MultipleKeys { .. } => None,
+            Self::Syntax(error) | Self::Base64(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "read error: {}", error),
+            Self::Syntax(error) | Self::Base64(error) => error.fmt(f),
+            Self::KeyNotFound { expected, found } => {
+                write!(f, "no {:?} section found", expected)?;
+                for label in found {
+                    write!(f, "; found {:?}, ", label)?;
+                    match key_reader(label) {
+                        Some(function) => write!(f, "which {}() reads", function)?,
+                        None => f.write_str("which private_key() does not read")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::NotFound { expected } => write!(f, "no {} found", expected),
+            Self::MultipleKeys { first, extra } => {
+                write!(f, "expected one private key, at line {}, but ", first)?;
+                match extra.as_slice() {
+                    [line] => write!(f, "found another at line {}", line),
+                    lines => {
+                        f.write_str("found others at lines ")?;
+                        for (i, line) in lines.iter().enumerate() {
+                            match i {
+                                0 => write!(f, "{}", line)?,
+                                _ => write!(f, ", {}", line)?,
+                            }
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Syntax(error) | Self::Base64(error) => error.source(),
+            Self::KeyNotFound { .. } | Self::NotFound { .. } | Self::MultipleKeys { .. } => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for ReadError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Base64Decode { .. } | Error::NonCanonicalPadding { .. } => Self::Base64(error),
+            _ => Self::Syntax(error),
+        }
+    }
+}
+
+/// An `io::Error` wrapping an [`Error`], as returned by [`read_one()`], is unwrapped again.
+#[cfg(feature = "std")]
+impl From<io::Error> for ReadError {
+    fn from(error: io::Error) -> Self {
+        match error.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            true => {
+                let inner = error.into_inner().unwrap();
+                Self::from(*inner.downcast::<Error>().unwrap())
+            }
+            false => Self::Io(error),
+        }
+    }
+}
+
+/// The inverse of `From<io::Error>`, for callers that return `io::Error`.
+#[cfg(feature = "std")]
+impl From<ReadError> for io::Error {
+    fn from(error: ReadError) -> Self {
+        match error {
+            ReadError::Io(error) => error,
+            ReadError::Syntax(error) | ReadError::Base64(error) => error.into(),
+            ReadError::KeyNotFound { .. }
+            | ReadError::NotFound { .. }
+            | ReadError::MultipleKeys { .. } => io::Error::new(ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+/// The helper function that reads private keys labelled `label`.
+#[cfg(feature = "std")]
+fn key_reader(label: &str) -> Option<&'static str> {
+    match label {
+        "RSA PRIVATE KEY" => Some("rsa_private_keys"),
+        "PRIVATE KEY" => Some("pkcs8_private_keys"),
+        "EC PRIVATE KEY" => Some("ec_private_keys"),
+        _ => None,
+    }
+}
+
+/// Extract and decode the next PEM section from `input`
+///
+/// - `Ok(None)` is returned if there is no PEM section to read from `input`
+/// - Syntax errors and decoding errors produce a `Err(...)`, giving where the error was
+///   found and where its section starts: see [`Error::position()`] and
+///   [`Error::section_start()`].  These are relative to the start of `input`, so when
+///   reading a remainder, add the length of the input before it to their offsets.
+/// - Otherwise each decoded section is returned with a `Ok(Some((Item::..., remainder)))` where
+///   `remainder` is the part of the `input` that follows the returned section
+pub fn read_one_from_slice(input: &[u8]) -> Result<Option<(Item, &[u8])>, Error> {
+    Item::from_slice(input, &ParserOptions::default())
+}
+
+/// Return an iterator over the PEM sections in `input`, extracting and decoding each.
+///
+/// This mirrors [`read_all()`] for input that is already in memory, without threading the
+/// remainder through calls to [`read_one_from_slice()`].  The positions in errors are
+/// relative to the start of `input`, and reading stops at the first error.  The iterator
+/// is a [`SliceReader`], so it can also be read from the back.
+pub fn read_all_from_slice(input: &[u8]) -> SliceReader<'_> {
+    SliceReader::new(input)
+}
+
+/// Extract and decode every PEM section in `input`, decoding their contents into `buf`.
+///
+/// This avoids allocating for each section when reading a large bundle from memory: the
+/// common kinds of item, such as certificates, borrow their contents from `buf`; see
+/// [`BorrowedItem`].  `buf` is cleared first, and may be reused for another input once
+/// the items are dropped.  Note that it holds the contents of any private keys read.
+///
+/// Errors are as for [`read_one_from_slice()`].  If there is an error, the contents of
+/// `buf` are unspecified.
+///
+/// ```
+/// use rustls_pemfile::{read_all_borrowed, BorrowedItem};
+///
+/// let pem = include_bytes!("../tests/data/certificate.chain.pem");
+/// let mut buf = Vec::new();
+/// let items = read_all_borrowed(pem, &mut buf).unwrap();
+/// assert!(items
+///     .iter()
+///     .all(|item| matches!(item, BorrowedItem::X509Certificate(_))));
+/// ```
+pub fn read_all_borrowed<'a>(
+    input: &[u8],
+    buf: &'a mut Vec<u8>,
+) -> Result<Vec<BorrowedItem<'a>>, Error> {
+    BorrowedItem::from_slice_into(input, &ParserOptions::default(), buf)
+}
+
+/// Extract and decode the next PEM section from `rd`.
+///
+/// - Ok(None) is returned if there is no PEM section read from `rd`.
+/// - Underlying IO errors produce a `Err(...)`
+/// - Syntax errors and decoding errors produce a `Err(...)` of kind `InvalidData`, which
+///   wraps the [`Error`].  Nothing is kept between calls, so its positions are relative to
+///   where this call started reading `rd`: use [`PemReader`] for positions in the whole
+///   input.
+/// - Otherwise each decoded section is returned with a `Ok(Some(Item::...))`
+///
+/// You can use this function to build an iterator, for example:
+/// `for item in iter::from_fn(|| read_one(rd).transpose()) { ... }`
+///
+/// UTF-16 input is not supported, as it cannot be transcoded a section at a time: its
+/// byte order mark produces a `Err(...)` of kind `InvalidData`, and nothing after it is
+/// read as PEM.  Use [`read_all()`] or [`PemReader`] for such input.  A UTF-8 byte order
+/// mark is not skipped, as no call knows whether it starts the input, but it is passed
+/// over like any other text before a section.
+#[cfg(feature = "std")]
+pub fn read_one(rd: &mut dyn io::BufRead) -> Result<Option<Item>, io::Error> {
+    reject_utf16(rd)?;
+    Item::from_buf(rd, &ParserOptions::default(), &mut ReadState::partway())
+}
+
+/// Extract and return all PEM sections by reading `rd`.
+#[cfg(feature = "std")]
+pub fn read_all(rd: &mut dyn io::BufRead) -> impl Iterator<Item = Result<Item, io::Error>> + '_ {
+    PemReader::new(rd)
+}
+
+/// Extract and decode the next PEM section from `rd`, with where it is in the input.
+///
+/// The result is otherwise as for [`read_one()`].  The [`Span`] is relative to the
+/// start of what is read from `rd` by this call, so to locate sections after the first,
+/// use [`read_all_with_spans()`] or [`PemReader::read_section()`] instead.
+#[cfg(feature = "std")]
+pub fn read_one_with_span(rd: &mut dyn io::BufRead) -> Result<Option<(Item, Span)>, io::Error> {
+    reject_utf16(rd)?;
+    Ok(
+        PemSection::from_buf(rd, &ParserOptions::default(), &mut ReadState::partway())?
+            .map(|section| (section.item, section.span)),
+    )
+}
+
+/// Fail if `rd` starts with a UTF-16 byte order mark, consuming it.
+///
+/// The transcoder's state would be lost between calls that read a section at a time.
+#[cfg(feature = "std")]
+fn reject_utf16(rd: &mut dyn io::BufRead) -> Result<(), io::Error> {
+    if Utf16::detect(rd)?.is_none() {
+        return Ok(());
+    }
+
+    rd.consume(2);
+    Err(io::Error::new(
+        ErrorKind::InvalidData,
+        "UTF-16 input is only supported by read_all() and PemReader",
+    ))
+}
+
+/// Extract and return all PEM sections by reading `rd`, each with where it is in the input.
+#[cfg(feature = "std")]
+pub fn read_all_with_spans(
+    rd: &mut dyn io::BufRead,
+) -> impl Iterator<Item = Result<(Item, Span), io::Error>> + '_ {
+    let mut reader = PemReader::new(rd);
+    core::iter::from_fn(move || {
+        reader
+            .read_section()
+            .map(|section| section.map(|section| (section.item, section.span)))
+            .transpose()
+    })
+}
+
+#[allow(clippy::type_complexity)]
+/// Read the next section from `input`, which starts at `position`.
+///
+/// On success, `position` is advanced to the start of the returned remainder of `input`.
+///
+/// The section's contents are decoded into `output`, after anything already there, and
+/// returned in the [`Section`]'s `data`.  If there is no section, `output` is left as it was.
+fn from_slice<'a>(
+    mut input: &'a [u8],
+    position: &mut Position,
+    options: &ParserOptions,
+    diagnostics: &mut Diagnostics,
+    output: &mut Vec<u8>,
+) -> Result<Option<(Section, &'a [u8])>, Error> {
+    let mut buffers = Buffers {
+        captured: Vec::new(),
+        output: core::mem::take(output),
+    };
+    let mut section = None::<OpenSection>;
+
+    // editors on Windows often start files with a UTF-8 byte order mark
+    if *position == Position::default() {
+        if let Some(rest) = input.strip_prefix(BOM) {
+            input = rest;
+            position.offset = BOM.len();
+        }
+    }
+
+    let (start, len) = (*position, input.len());
+    let mut line_number = start.line;
+
+    loop {
+        let line_position = Position {
+            offset: start.offset + len - input.len(),
+            line: line_number,
+        };
+        let line_start = input;
+        let newline = input
+            .iter()
+            .position(|byte| *byte == b'\n' || *byte == b'\r')
+            .map(|index| (index, 1));
+        let escaped = match options.unescape_newlines {
+            true => escaped_newline(&input[..newline.map_or(input.len(), |(index, _)| index)])
+                .map(|index| (index, 2)),
+            false => None,
+        };
+
+        let terminator = match escaped.or(newline) {
+            Some(terminator) => Some(terminator),
+            // the last line need not be terminated
+            None if !input.is_empty() => Some((input.len(), 0)),
+            None => None,
+        };
+
+        let next_line = if let Some((index, skip)) = terminator {
+            let cut = match options.strict {
+                true => index,
+                false => logical_line_len(&input[..index]),
+            };
+
+            if cut == index && input.get(index) == Some(&b'\n') {
+                line_number += 1;
+            }
+
+            let (line, newline_plus_remainder) = input.split_at(cut);
+            input = match cut == index {
+                true => &newline_plus_remainder[skip..],
+                false => newline_plus_remainder,
+            };
+            Some(line)
+        } else {
+            None
+        };
+
+        if let (Some(line), Some(max)) = (next_line, options.max_line_length) {
+            if line.len() > max {
+                return Err(Error::LineTooLong {
+                    position: line_position,
+                    section: section.as_ref().map(|open| open.position),
+                });
+            }
+        }
+
+        let opening = section.is_none();
+        let (item, rest, rest_position) = match read(
+            next_line,
+            line_position,
+            &mut section,
+            &mut buffers,
+            options,
+            diagnostics,
+        ) {
+            Ok(ControlFlow::Continue(())) => {
+                // the whole body is at hand, so room for its contents can be made at once
+                if let Some(open) = section.as_mut().filter(|open| opening && !open.skip) {
+                    open.data.reserve_exact(decoded_len(input));
+                }
+                continue;
+            }
+            Ok(ControlFlow::Break(Some(item))) if item.reread_line => {
+                (Some(item), line_start, line_position)
+            }
+            Ok(ControlFlow::Break(item)) => (
+                item,
+                input,
+                Position {
+                    offset: start.offset + len - input.len(),
+                    line: line_number,
+                },
+            ),
+            Err(err) => return Err(err),
+        };
+
+        *position = rest_position;
+        if item.is_none() {
+            *output = buffers.output;
+        }
+        return Ok(item.map(|item| (item, rest)));
+    }
+}
+
+/// Problems found in the input that did not stop it being read.
+#[derive(Debug, Default)]
+pub(crate) struct Diagnostics {
+    /// The errors in sections skipped so far.
+    pub(crate) errors: Vec<Error>,
+    /// The warnings so far, if they are being collected.
+    pub(crate) warnings: Vec<Warning>,
+    /// The certificates read so far, if warnings are being collected.
+    certificates: BTreeSet<Vec<u8>>,
+}
+
+impl Diagnostics {
+    fn warn(&mut self, options: &ParserOptions, warning: impl FnOnce() -> Warning) {
+        if options.collect_warnings {
+            self.warnings.push(warning());
+        }
+    }
+
+    /// Check `section` for duplicates of those read before.
+    fn section(&mut self, section: &Section, options: &ParserOptions) {
+        if !options.collect_warnings || section.kind != Some(SectionKind::Certificate) {
+            return;
+        }
+
+        if !self
+            .certificates
+            .insert(section.data[section.start..].to_vec())
+        {
+            self.warn(options, || Warning::DuplicateCertificate {
+                line: section.position.line,
+            });
+        }
+    }
+}
+
+/// A position in PEM input.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// The line number, counting from one.
+    pub line: usize,
+    /// The offset in bytes from the start of the input.
+    pub offset: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { offset: 0, line: 1 }
+    }
+}
+
+/// Where a section is in PEM input.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// The position of the start of the section's BEGIN line.
+    pub start: Position,
+    /// The position of the end of the section's END line, excluding any line ending.
+    ///
+    /// So the section occupies the bytes from `start.offset` up to `end.offset`, and
+    /// the lines from `start.line` to `end.line` inclusive.
+    pub end: Position,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} (byte offset {})", self.line, self.offset)
+    }
+}
+
+/// What is carried over between calls to [`from_buf()`] on the same reader.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub(crate) struct ReadState {
+    /// Input that was read but not used by the previous call, after the first `used` bytes.
+    ///
+    /// The buffer is kept between calls, so lines are read without allocating.
+    pending: Vec<u8>,
+    /// How much of `pending` was used by the previous call.
+    used: usize,
+    /// The position in the input of the start of the unused part of `pending`.
+    position: Position,
+    /// What has been read so far, for the limits on the whole input.
+    totals: Totals,
+    /// Set if the input was found to be UTF-16.
+    utf16: Option<Utf16>,
+    /// Set if reading may start partway through the input, where a byte order mark is
+    /// not skipped, because nothing is kept from one section to the next.
+    partway: bool,
+    pub(crate) diagnostics: Diagnostics,
+}
+
+#[cfg(feature = "std")]
+impl ReadState {
+    /// State for reading a single section from wherever the input has got to.
+    fn partway() -> Self {
+        Self {
+            partway: true,
+            ..Self::default()
+        }
+    }
+
+    /// Start reading another input, keeping the buffers.
+    pub(crate) fn reset(&mut self) {
+        self.pending.clear();
+        self.used = 0;
+        self.position = Position::default();
+        self.totals = Totals::default();
+        self.utf16 = None;
+        self.diagnostics.errors.clear();
+        self.diagnostics.warnings.clear();
+        self.diagnostics.certificates.clear();
+    }
+
+    /// Account for `section` having been read, checking it against the limits in `options`.
+    fn section(&mut self, section: Section, options: &ParserOptions) -> Result<Section, Error> {
+        self.totals.section(&section, options)?;
+        Ok(section)
+    }
+}
+
+/// The number of sections read so far, and the total length of their contents, for
+/// [`ParserOptions::max_sections()`] and [`ParserOptions::max_total_decoded()`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Totals {
+    sections: usize,
+    decoded: usize,
+}
+
+impl Totals {
+    /// Account for `section` having been read, checking it against the limits in `options`.
+    fn section(&mut self, section: &Section, options: &ParserOptions) -> Result<(), Error> {
+        self.sections += 1;
+        if matches!(options.max_sections, Some(max) if self.sections > max) {
+            return Err(Error::TooManySections {
+                position: section.position,
+            });
+        }
+
+        let len = section.data.len() - section.start;
+        self.decoded = self.decoded.saturating_add(len);
+        if matches!(options.max_total_decoded, Some(max) if self.decoded > max) {
+            return Err(Error::DecodedBudgetExceeded {
+                position: section.position,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Add the totals for a later part of the input, unless the sum is over the limits in
+    /// `options`, returning whether it was added.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn add(&mut self, later: Totals, options: &ParserOptions) -> bool {
+        let sum = Self {
+            sections: self.sections + later.sections,
+            decoded: self.decoded.saturating_add(later.decoded),
+        };
+        let within = !matches!(options.max_sections, Some(max) if sum.sections > max)
+            && !matches!(options.max_total_decoded, Some(max) if sum.decoded > max);
+        if within {
+            *self = sum;
+        }
+        within
+    }
+}
+
+/// Read the next section from `rd`, continuing from `state`.
+///
+/// This is generic over the reader, so that reading from a concrete type is not
+/// dispatched dynamically for every line.
+#[cfg(feature = "std")]
+fn from_buf<R: io::BufRead + ?Sized>(
+    rd: &mut R,
+    options: &ParserOptions,
+    state: &mut ReadState,
+) -> Result<Option<Section>, io::Error> {
+    // files exported on Windows are sometimes UTF-16, with a byte order mark
+    if state.position.offset == 0 && state.utf16.is_none() {
+        state.utf16 = Utf16::detect(rd)?;
+    }
+
+    match state.utf16.take() {
+        Some(mut utf16) => {
+            let mut transcoded = Utf16Reader {
+                rd,
+                utf16: &mut utf16,
+            };
+            let result = from_lines(&mut transcoded, options, state);
+            state.utf16 = Some(utf16);
+            result
+        }
+        None => from_lines(rd, options, state),
+    }
+}
+
+/// Read the next section from the lines of `rd`, which is UTF-8.
+#[cfg(feature = "std")]
+fn from_lines<R: io::BufRead + ?Sized>(
+    rd: &mut R,
+    options: &ParserOptions,
+    state: &mut ReadState,
+) -> Result<Option<Section>, io::Error> {
+    let mut buffers = Buffers::default();
+    let mut section = None::<OpenSection>;
+    let mut line = core::mem::take(&mut state.pending);
+    line.drain(..core::mem::take(&mut state.used));
+    let position = &mut state.position;
+
+    loop {
+        // a BEGIN line that ends an unterminated section is left unread if it is at hand,
+        // so that it is read again by a caller that keeps no state, like `read_one()`
+        if line.is_empty()
+            && ((section.is_some()
+                && matches!(rd.fill_buf(), Ok(buf) if begins_another(buf, options)))
+                || read_until_newline(
+                    rd,
+                    &mut line,
+                    !options.strict,
+                    options.max_line_length,
+                    *position,
+                    section.as_ref().map(|open| open.position),
+                )? == 0)
+        {
+            match read(
+                None,
+                *position,
+                &mut section,
+                &mut buffers,
+                options,
+                &mut state.diagnostics,
+            )? {
+                ControlFlow::Break(opt) => {
+                    state.pending = line;
+                    return Ok(opt);
+                }
+                ControlFlow::Continue(()) => continue,
+            }
+        }
+
+        // editors on Windows often start files with a UTF-8 byte order mark
+        if position.offset == 0 && !state.partway && line.starts_with(BOM) {
+            line.drain(..BOM.len());
+            position.offset = BOM.len();
+        }
+
+        // `read_until_newline` splits off encapsulation boundaries, unless
+        // one straddled the edge of the reader's buffer
+        let mut rest = &line[..];
+        while !rest.is_empty() {
+            let cut = match options.strict {
+                true => rest.len(),
+                false => logical_line_len(rest),
+            };
+            let (cut, skip) = match options.unescape_newlines {
+                true => escaped_newline(&rest[..cut]).map_or((cut, 0), |index| (index, 2)),
+                false => (cut, 0),
+            };
+
+            let line_start = rest;
+            let (next_line, after) = rest.split_at(cut);
+            rest = &after[skip..];
+            let line_position = *position;
+            position.offset += cut + skip;
+            position.line += next_line.iter().filter(|&&b| b == b'\n').count();
+            if let ControlFlow::Break(opt) = read(
+                Some(next_line),
+                line_position,
+                &mut section,
+                &mut buffers,
+                options,
+                &mut state.diagnostics,
+            )? {
+                state.used = match &opt {
+                    Some(section) if section.reread_line => {
+                        *position = line_position;
+                        line.len() - line_start.len()
+                    }
+                    _ => line.len() - rest.len(),
+                };
+                state.pending = line;
+                return Ok(opt);
+            }
+        }
+
+        line.clear();
+    }
+}
+
+/// The length of the first logical line in `line`.
+///
+/// Encapsulation boundaries are treated as lines of their own, even without line
+/// breaks around them, so PEM that has been collapsed onto a single line can be read,
+/// as can sections concatenated without a line break between them.
+pub(crate) fn logical_line_len(line: &[u8]) -> usize {
+    LineScanner::default().len(line)
+}
+
+/// A scan for the end of the first logical line of a buffer that grows between scans.
+///
+/// Each scan starts where the last one left off, so finding the end of a long line
+/// that is read a chunk at a time takes time linear in its length.
+#[derive(Debug, Default)]
+pub(crate) struct LineScanner {
+    /// The length of the buffer at the last scan.
+    scanned: usize,
+    /// The end of the boundary that the line starts with, once found.
+    close: Option<usize>,
+    /// The start of the text after that boundary, once found.
+    after: Option<usize>,
+    /// The end of the line, once found.
+    end: Option<usize>,
+}
+
+impl LineScanner {
+    const MARKERS: [&'static [u8]; 2] = [b"-----BEGIN ", b"-----END "];
+
+    /// The length of the first logical line in `line`, which starts with the buffer
+    /// passed to the previous scan; see [`logical_line_len()`].
+    pub(crate) fn len(&mut self, line: &[u8]) -> usize {
+        if self.end.is_none() {
+            let scanned = core::mem::replace(&mut self.scanned, line.len());
+            self.end = match Self::MARKERS.iter().find(|marker| line.starts_with(marker)) {
+                Some(marker) => self.boundary_end(line, marker.len(), scanned),
+                // a marker may straddle the end of what was scanned before
+                None => Self::MARKERS
+                    .iter()
+                    .filter_map(|marker| {
+                        let from = scanned.saturating_sub(marker.len() - 1).max(1);
+                        Some(from + find(line.get(from..)?, marker)?)
+                    })
+                    .min(),
+            };
+        }
+
+        self.end.unwrap_or(line.len())
+    }
+
+    /// The end of the boundary that the last line scanned starts with, if what follows it
+    /// in `line` may yet turn out to begin another line, when more is read.
+    #[cfg(feature = "std")]
+    fn tentative_end(&self, line: &[u8]) -> Option<usize> {
+        let after = match (self.end, self.after) {
+            (None, Some(start)) => &line[start..],
+            (None, None) => b"",
+            (Some(_), _) => return None,
+        };
+
+        Self::MARKERS
+            .iter()
+            .any(|marker| marker.starts_with(after))
+            .then_some(self.close?)
+    }
+
+    /// The end of the boundary that `line` starts with, after a marker of length `len`,
+    /// if it is followed by another line.
+    fn boundary_end(&mut self, line: &[u8], len: usize, scanned: usize) -> Option<usize> {
+        let close = match self.close {
+            Some(close) => close,
+            None => {
+                let from = scanned.saturating_sub(4).max(len);
+                let close = from + find(&line[from..], b"-----")? + 5;
+                *self.close.insert(close)
+            }
+        };
+
+        // anything after the boundary is a line of its own, except more dashes
+        // (which make the boundary malformed) that do not start another boundary
+        let start = match self.after {
+            Some(start) => start,
+            None => {
+                let from = scanned.max(close);
+                let index = line[from..].iter().position(|b| !b.is_ascii_whitespace())?;
+                *self.after.insert(from + index)
+            }
+        };
+
+        let after = &line[start..];
+        match after.starts_with(b"-") {
+            true => Self::MARKERS
+                .iter()
+                .any(|marker| after.starts_with(marker))
+                .then_some(close),
+            false => Some(close),
+        }
+    }
+}
+
+/// The kind of a section labelled `label`, if it is recognised.
+fn section_kind(label: &[u8], options: &ParserOptions) -> Option<SectionKind> {
+    match options.ignore_label_case {
+        true => SectionKind::try_from(&label.to_ascii_uppercase()[..]),
+        false => SectionKind::try_from(label),
+    }
+    .ok()
+}
+
+/// Whether errors in a section labelled `label` have content from the input removed.
+fn redacts(label: &[u8], options: &ParserOptions) -> bool {
+    match options.redaction {
+        Redaction::Secrets => section_kind(label, options).is_none_or(|kind| kind.secret()),
+        Redaction::Always => true,
+        Redaction::Never => false,
+    }
+}
+
+/// `error`, found decoding a section labelled `label`, unless it must be redacted.
+fn redact(error: base64::Error, label: &[u8], options: &ParserOptions) -> Option<base64::Error> {
+    match error {
+        base64::Error::InvalidCharacter(_) if redacts(label, options) => None,
+        _ => Some(error),
+    }
+}
+
+/// Whether `line`, read in an open section, implicitly ends it by beginning another.
+///
+/// This allows recovery from a missing END line, except in strict mode.
+fn begins_another(line: &[u8], options: &ParserOptions) -> bool {
+    !options.strict && starts_with(line, b"-----BEGIN ", options.ignore_label_case)
+}
+
+/// The length of the contents of a section whose body starts `input`, from the length of
+/// its text up to the next `-`, which starts its END line.
+///
+/// This is exact for a body of base64 alone, and otherwise only an estimate.  The scan
+/// stops at the end of the section however its lines end, so reading every section costs
+/// no more than reading the input once.  Only whitespace, padding and `-` are
+/// distinguished, so the body's value is not leaked.
+fn decoded_len(input: &[u8]) -> usize {
+    let symbols = input
+        .iter()
+        .take_while(|&&b| b != b'-')
+        .filter(|&&b| !b.is_ascii_whitespace() && b != b'=')
+        .count();
+    symbols * 3 / 4
+}
+
+/// The position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[allow(clippy::type_complexity)]
+fn read(
+    next_line: Option<&[u8]>,
+    position: Position,
+    section: &mut Option<OpenSection>,
+    buffers: &mut Buffers,
+    options: &ParserOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<ControlFlow<Option<Section>, ()>, Error> {
+    let line = if let Some(line) = next_line {
+        line
+    } else {
+        // EOF
+        let open = match section.take() {
+            Some(open) => open,
+            None => return Ok(ControlFlow::Break(None)),
+        };
+
+        let err = match open.error {
+            Some(err) => err,
+            None => Error::MissingSectionEnd {
+                end_marker: open.end_marker(),
+                position: open.position,
+            },
+        };
+        return match options.skip_malformed {
+            true => Ok(ControlFlow::Break(Some(Section::failed(err)))),
+            false => Err(err),
+        };
+    };
+
+    let normalized;
+    let line = match options.normalize_markers && !line.is_ascii() {
+        true => {
+            normalized = normalize_marker(line);
+            match normalized.starts_with(b"----") {
+                true => &normalized[..],
+                false => line,
+            }
+        }
+        false => line,
+    };
+
+    if matches!(strip_eol(line).last(), Some(b' ' | b'\t')) {
+        diagnostics.warn(options, || Warning::TrailingWhitespace {
+            line: position.line,
+        });
+    }
+
+    if !options.skip_malformed {
+        return read_line(line, position, section, buffers, options, diagnostics);
+    }
+
+    // after an error, skip the rest of the section
+    let ignore_case = options.ignore_label_case;
+    if let Some(open) = section.as_mut() {
+        if let Some(err) = open.error.take() {
+            if open.ends(line, ignore_case) {
+                *section = None;
+                return Ok(ControlFlow::Break(Some(Section::failed(err))));
+            }
+
+            if begins_another(line, options) {
+                let mut failed = Section::failed(err);
+                failed.reread_line = true;
+                *section = None;
+                return Ok(ControlFlow::Break(Some(failed)));
+            }
+
+            open.error = Some(err);
+            return Ok(ControlFlow::Continue(()));
+        }
+    }
+
+    let err = match read_line(line, position, section, buffers, options, diagnostics) {
+        Err(err) => err,
+        result => return result,
+    };
+
+    match section.as_mut() {
+        Some(open) if !open.ends(line, ignore_case) => {
+            open.error = Some(err);
+            Ok(ControlFlow::Continue(()))
+        }
+        _ => {
+            *section = None;
+            Ok(ControlFlow::Break(Some(Section::failed(err))))
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn read_line(
+    line: &[u8],
+    position: Position,
+    section: &mut Option<OpenSection>,
+    buffers: &mut Buffers,
+    options: &ParserOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<ControlFlow<Option<Section>, ()>, Error> {
+    let ignore_case = options.ignore_label_case;
+    if section.is_some() && begins_another(line, options) {
+        if let Some(open) = section.take() {
+            return Ok(ControlFlow::Break(Some(Section::unterminated(open))));
+        }
+    }
+
+    if let Some(open) = section {
+        if starts_with(line, b"-----BEGIN ", ignore_case) {
+            return Err(Error::NestedSectionStart {
+                label: String::from_utf8_lossy(&open.label).into_owned(),
+                position,
+                section: open.position,
+            });
+        }
+    }
+
+    if starts_with(line, b"-----BEGIN ", ignore_case) {
+        let (mut trailer, mut pos) = (0, line.len());
+        for (i, &b) in line.iter().enumerate().rev() {
+            match b {
+                b'-' => {
+                    trailer += 1;
+                    pos = i;
+                }
+                b'\n' | b'\r' | b' ' => continue,
+                _ => break,
+            }
+        }
+
+        if trailer != 5 {
+            let label = line[11..].split(|&b| b == b'-').next().unwrap_or_default();
+            let line = strip_eol(line);
+            return Err(Error::IllegalSectionStart {
+                line: match redacts(trim_end(label), options) {
+                    true => None,
+                    false => Some(line.to_vec()),
+                },
+                length: line.len(),
+                position,
+            });
+        }
+
+        if options.strict && strip_eol(line).len() != pos + 5 {
+            return Err(Error::UnexpectedWhitespace {
+                position,
+                section: position,
+            });
+        }
+
+        let ty = &line[11..pos];
+        if ty.iter().any(u8::is_ascii_lowercase) {
+            diagnostics.warn(options, || Warning::LowercaseLabel {
+                line: position.line,
+                label: String::from_utf8_lossy(ty).into_owned(),
+            });
+        }
+
+        let encapsulation = match starts_with(ty, b"PGP ", ignore_case) {
+            true => Encapsulation::OpenPgp,
+            false => Encapsulation::Rfc7468,
+        };
+        let mut open = OpenSection::new(ty.to_owned(), encapsulation, options);
+        open.begin(buffers);
+        open.position = position;
+        *section = Some(open);
+        return Ok(ControlFlow::Continue(()));
+    }
+
+    let trimmed = trim_end(line);
+    if trimmed.len() == RFC4716_BEGIN.len() && starts_with(trimmed, RFC4716_BEGIN, ignore_case) {
+        let mut open = OpenSection::new(RFC4716_LABEL.to_vec(), Encapsulation::Rfc4716, options);
+        open.begin(buffers);
+        open.position = position;
+        *section = Some(open);
+        return Ok(ControlFlow::Continue(()));
+    }
+
+    if let Some(open) = section.as_mut() {
+        // a section that is not wanted is skipped without being decoded
+        if open.skip {
+            if open.ends(line, ignore_case) {
+                buffers.output = core::mem::take(&mut open.data);
+                *section = None;
+            }
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        if open.ends(line, ignore_case) {
+            let kind = section_kind(&open.label, options);
+            if kind.is_none() {
+                diagnostics.warn(options, || Warning::UnknownLabel {
+                    line: open.position.line,
+                    label: String::from_utf8_lossy(&open.label).into_owned(),
+                });
+            }
+
+            #[cfg(feature = "parallel")]
+            if let Some(body) = open.held.take() {
+                if let Err((error, position)) = body.decode(&mut open.decoder, &mut open.data) {
+                    return Err(Error::Base64Decode {
+                        error: redact(error, &open.label, options),
+                        label: String::from_utf8_lossy(&open.label).into_owned(),
+                        position,
+                        section: open.position,
+                    });
+                }
+            }
+
+            if let Some(err) = open.undecodable.take() {
+                return Err(err);
+            }
+
+            let mut der = core::mem::take(&mut open.data);
+            let decoder = core::mem::replace(&mut open.decoder, base64::Decoder::new(true));
+            let canonical = decoder.finish(&mut der).map_err(|error| {
+                // an error at the end of the body is placed at its last character
+                Error::Base64Decode {
+                    error: redact(error, &open.label, options),
+                    label: String::from_utf8_lossy(&open.label).into_owned(),
+                    position: open.last.unwrap_or(position),
+                    section: open.position,
+                }
+            })?;
+
+            if options.strict && open.encapsulation == Encapsulation::Rfc7468 && !canonical {
+                return Err(Error::NonCanonicalPadding {
+                    label: String::from_utf8_lossy(&open.label).into_owned(),
+                    position: open.position,
+                });
+            }
+
+            if let Some(expected) = open.checksum {
+                if crc24(&der[open.start..]) != expected {
+                    return Err(Error::InvalidArmorChecksum {
+                        label: String::from_utf8_lossy(&open.label).into_owned(),
+                        position: open.position,
+                    });
+                }
+            }
+
+            let headers = core::mem::take(&mut open.headers);
+            return Ok(ControlFlow::Break(Some(Section {
+                label: core::mem::take(&mut open.label),
+                kind,
+                data: der,
+                start: open.start,
+                headers,
+                preceding: core::mem::take(&mut open.preceding),
+                position: open.position,
+                end: Position {
+                    offset: position.offset + strip_eol(line).len(),
+                    ..position
+                },
+                error: None,
+                reread_line: false,
+            })));
+        }
+
+        // header lines count towards the limit too, so that they cannot grow without bound
+        if let Some(max) = options.max_section_bytes {
+            if open.body_len + line.len() > max {
+                return Err(Error::SectionTooLarge {
+                    position,
+                    section: Some(open.position),
+                });
+            }
+        }
+        open.body_len += line.len();
+
+        match open.encapsulation {
+            Encapsulation::Rfc7468 if options.strict => {
+                let line = strip_eol(line);
+                if line.iter().any(|&b| matches!(b, b' ' | b'\t')) {
+                    return Err(Error::UnexpectedWhitespace {
+                        position,
+                        section: open.position,
+                    });
+                }
+
+                // every line but the last must be exactly 64 characters; lines
+                // may appear empty here if they end in CRLF
+                match line.len() {
+                    0 => {}
+                    _ if open.short_line => {
+                        return Err(Error::InvalidLineLength {
+                            position,
+                            section: open.position,
+                        })
+                    }
+                    65.. => {
+                        return Err(Error::InvalidLineLength {
+                            position,
+                            section: open.position,
+                        })
+                    }
+                    64 => {}
+                    _ => open.short_line = true,
+                }
+            }
+            // RFC 1421 encapsulated headers come before the body
+            Encapsulation::Rfc7468 => {
+                if open.header(trim_end(line)) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+
+                let length = trim_end(line).len();
+                let irregular = match length {
+                    0 => false,
+                    _ if open.short_line => true,
+                    65.. => true,
+                    64 => false,
+                    _ => {
+                        open.short_line = true;
+                        false
+                    }
+                };
+                if irregular {
+                    diagnostics.warn(options, || Warning::LineLength {
+                        line: position.line,
+                        length,
+                    });
+                }
+            }
+            Encapsulation::Rfc4716 => {
+                if open.header(trim_end(line)) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+            }
+            Encapsulation::OpenPgp => {
+                let line = trim_end(line);
+                if let Some(checksum) = line.strip_prefix(b"=") {
+                    let mut crc = [0u8; 3];
+                    base64::decode_public(checksum, &mut crc).map_err(|(error, index)| {
+                        Error::Base64Decode {
+                            error: redact(error, &open.label, options),
+                            label: String::from_utf8_lossy(&open.label).into_owned(),
+                            // the checksum follows the `=`
+                            position: Position {
+                                offset: position.offset + 1 + index,
+                                ..position
+                            },
+                            section: open.position,
+                        }
+                    })?;
+                    open.checksum = Some(u32::from_be_bytes([0, crc[0], crc[1], crc[2]]));
+                    return Ok(ControlFlow::Continue(()));
+                }
+
+                if open.header(line) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+            }
+        }
+
+        if let Some(index) = line.iter().rposition(|b| !b.is_ascii_whitespace()) {
+            open.last = Some(Position {
+                offset: position.offset + index,
+                ..position
+            });
+        }
+
+        #[cfg(feature = "parallel")]
+        if let Some(body) = open.held.as_mut() {
+            body.push(line, position);
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        // the first error is reported at the END line, after any found in later lines
+        if open.undecodable.is_none() {
+            if let Err((error, index)) = open.decoder.update(line, &mut open.data) {
+                open.undecodable = Some(Error::Base64Decode {
+                    error: redact(error, &open.label, options),
+                    label: String::from_utf8_lossy(&open.label).into_owned(),
+                    position: Position {
+                        offset: position.offset + index,
+                        ..position
+                    },
+                    section: open.position,
+                });
+            }
+        }
+    } else {
+        let text = match options.strict {
+            true => strip_eol(line).first().map(|_| 0),
+            false if options.reject_text_outside_sections => {
+                line.iter().position(|b| !b.is_ascii_whitespace())
+            }
+            false => None,
+        };
+
+        if let Some(index) = text {
+            let position = Position {
+                offset: position.offset + index,
+                ..position
+            };
+            return Err(Error::TextOutsideSection { position });
+        }
+
+        // outside sections, `buffers.captured` holds any text being captured
+        let captured = &mut buffers.captured;
+        if options.capture_preceding_text {
+            let line = strip_eol(line);
+            if matches!(options.max_section_bytes, Some(max) if captured.len() + line.len() >= max)
+            {
+                return Err(Error::SectionTooLarge {
+                    position,
+                    section: None,
+                });
+            }
+
+            captured.extend_from_slice(line);
+            captured.push(b'\n');
+        }
+    }
+
+    Ok(ControlFlow::Continue(()))
+}
+
+impl Section {
+    /// A section that could not be read, because of `error`.
+    fn failed(error: Error) -> Self {
+        Self {
+            label: Vec::new(),
+            kind: None,
+            data: Vec::new(),
+            start: 0,
+            headers: Vec::new(),
+            preceding: Vec::new(),
+            position: error.position(),
+            end: error.position(),
+            error: Some(error),
+            reread_line: false,
+        }
+    }
+
+    /// A section that was not ended before the BEGIN line of another.
+    fn unterminated(open: OpenSection) -> Self {
+        let mut section = Self::failed(Error::MissingSectionEnd {
+            end_marker: open.end_marker(),
+            position: open.position,
+        });
+        section.reread_line = true;
+        section
+    }
+}
+
+impl OpenSection {
+    /// Start reading the section, taking the text captured before it and the buffer to
+    /// decode into from `buffers`.
+    fn begin(&mut self, buffers: &mut Buffers) {
+        self.preceding = core::mem::take(&mut buffers.captured);
+        self.data = core::mem::take(&mut buffers.output);
+        self.start = self.data.len();
+    }
+
+    fn new(label: Vec<u8>, encapsulation: Encapsulation, options: &ParserOptions) -> Self {
+        // unrecognised sections could contain anything, so are treated as secret
+        let kind = section_kind(&label, options);
+        let secret = match kind {
+            Some(kind) => kind.secret(),
+            None => true,
+        };
+        let skip = match (options.only, kind) {
+            (Some(only), Some(kind)) => !only.contains(&kind),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        Self {
+            label,
+            encapsulation,
+            headers: Vec::new(),
+            continued: false,
+            checksum: None,
+            short_line: false,
+            body: false,
+            preceding: Vec::new(),
+            position: Position::default(),
+            decoder: base64::Decoder::new(secret),
+            data: Vec::new(),
+            start: 0,
+            body_len: 0,
+            last: None,
+            undecodable: None,
+            #[cfg(feature = "parallel")]
+            held: options.decode_in_parallel.then(Body::default),
+            skip,
+            error: None,
+        }
+    }
+
+    /// Whether `line` is this section's END line.
+    ///
+    /// This is matched in place, rather than against an allocated marker.
+    fn ends(&self, line: &[u8], ignore_case: bool) -> bool {
+        if self.encapsulation == Encapsulation::Rfc4716 {
+            return starts_with(line, RFC4716_END, ignore_case);
+        }
+
+        starts_with(line, b"-----END ", ignore_case)
+            && starts_with(&line[9..], &self.label, ignore_case)
+            && starts_with(&line[9 + self.label.len()..], b"-----", ignore_case)
+    }
+
+    /// The END line expected, for reporting that it is missing.
+    fn end_marker(&self) -> Vec<u8> {
+        match self.encapsulation {
+            Encapsulation::Rfc4716 => RFC4716_END.to_vec(),
+            _ => [&b"-----END "[..], &self.label, b"-----"].concat(),
+        }
+    }
+
+    /// Consumes `line` if it is a header line, returning whether it was.
+    ///
+    /// Header lines are identified by containing a colon (which base64 never does),
+    /// by following a header line that ended in a backslash, or by starting with
+    /// whitespace after a header line.  The last is an RFC 822 style folded line, which
+    /// is joined to the value with a single space.  Headers end at the first other line.
+    fn header(&mut self, line: &[u8]) -> bool {
+        if self.body {
+            return false;
+        }
+
+        let continues = self.continued;
+        let (line, continued) = match line.strip_suffix(b"\\") {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let folded = !self.headers.is_empty() && matches!(line.first(), Some(b' ' | b'\t'));
+
+        if continues {
+            if let Some((_, value)) = self.headers.last_mut() {
+                value.push_str(&String::from_utf8_lossy(line));
+            }
+        } else if folded {
+            if let Some((_, value)) = self.headers.last_mut() {
+                value.push(' ');
+                value.push_str(String::from_utf8_lossy(line).trim_start());
+            }
+        } else {
+            let colon = match line.iter().position(|&b| b == b':') {
+                Some(colon) => colon,
+                None => {
+                    self.body = true;
+                    return false;
+                }
+            };
+
+            let (tag, value) = (&line[..colon], &line[colon + 1..]);
+            self.headers.push((
+                String::from_utf8_lossy(tag).trim().to_owned(),
+                String::from_utf8_lossy(value).trim_start().to_owned(),
+            ));
+        }
+
+        self.continued = continued;
+        true
+    }
+}
+
+/// Replace Unicode characters commonly substituted for `-` and ` ` in `line`.
+///
+/// These are introduced when PEM is copied through word processors, wikis and chat clients.
+fn normalize_marker(line: &[u8]) -> Vec<u8> {
+    String::from_utf8_lossy(line)
+        .chars()
+        .map(|ch| match ch {
+            // hyphen, non-breaking hyphen, figure dash, en dash, em dash, horizontal bar,
+            // minus sign, and small and full-width hyphen-minus
+            '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{fe63}' | '\u{ff0d}' => '-',
+            // no-break space, figure space, and narrow no-break space
+            '\u{a0}' | '\u{2007}' | '\u{202f}' => ' ',
+            ch => ch,
+        })
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// Whether `line` starts with `prefix`, optionally ignoring ASCII case.
+fn starts_with(line: &[u8], prefix: &[u8], ignore_case: bool) -> bool {
+    match line.get(..prefix.len()) {
+        Some(start) if ignore_case => start.eq_ignore_ascii_case(prefix),
+        Some(start) => start == prefix,
+        None => false,
+    }
+}
+
+/// Remove any line ending from the end of `line`.
+fn strip_eol(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|&b| !matches!(b, b'\n' | b'\r'))
+        .map_or(0, |i| i + 1);
+    &line[..end]
+}
+
+pub(crate) fn trim_end(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|&b| !matches!(b, b'\n' | b'\r' | b' ' | b'\t'))
+        .map_or(0, |i| i + 1);
+    &line[..end]
+}
+
+/// The CRC-24 used by OpenPGP armor; as specified in RFC 4880 section 6.1
+pub(crate) fn crc24(data: &[u8]) -> u32 {
+    const INIT: u32 = 0xb7_04ce;
+    const POLY: u32 = 0x186_4cfb;
+
+    let mut crc = INIT;
+    for &byte in data {
+        crc ^= u32::from(byte) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= POLY;
+            }
+        }
+    }
+
+    crc & 0xff_ffff
+}
+
+const RFC4716_LABEL: &[u8] = b"SSH2 PUBLIC KEY";
+pub(crate) const RFC4716_BEGIN: &[u8] = b"---- BEGIN SSH2 PUBLIC KEY ----";
+pub(crate) const RFC4716_END: &[u8] = b"---- END SSH2 PUBLIC KEY ----";
+
+/// The position of the first `\n` or `\r` escape sequence in `line`.
+fn escaped_newline(line: &[u8]) -> Option<usize> {
+    line.windows(2)
+        .position(|pair| matches!(pair, [b'\\', b'n' | b'r']))
+}
+
+/// The UTF-8 encoding of U+FEFF, the byte order mark.
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+// Ported from https://github.com/rust-lang/rust/blob/91cfcb021935853caa06698b759c293c09d1e96a/library/std/src/io/mod.rs#L1990 and
+// modified to look for our accepted newlines.
+//
+// If `split` is true, this also stops before any encapsulation boundary that does not start
+// a line, or after one that is (or may yet be) followed by more text; see `logical_line_len`.
+// Nothing after the line is consumed from `r`.
+#[cfg(feature = "std")]
+fn read_until_newline<R: io::BufRead + ?Sized>(
+    r: &mut R,
+    buf: &mut Vec<u8>,
+    split: bool,
+    max: Option<usize>,
+    position: Position,
+    section: Option<Position>,
+) -> io::Result<usize> {
+    let mut read = 0;
+    // where the logical line being scanned for starts in `buf`
+    let mut line_start = 0;
+    let mut scanner = LineScanner::default();
+    loop {
+        let (done, used) = {
+            let available = match r.fill_buf() {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let newline = available
+                .iter()
+                .copied()
+                .position(|b| b == b'\n' || b == b'\r');
+            let end = newline.unwrap_or(available.len());
+
+            let start = buf.len();
+            buf.extend_from_slice(&available[..end]);
+            let mut cut = buf.len();
+            if split {
+                // a boundary that straddled the edge of what was read before ends a line
+                // that was consumed already, so look for the end of the next one
+                loop {
+                    cut = line_start + scanner.len(&buf[line_start..]);
+                    if cut >= start || cut == buf.len() {
+                        break;
+                    }
+                    line_start = cut;
+                    scanner = LineScanner::default();
+                }
+            }
+
+            if matches!(max, Some(max) if cut - line_start > max) {
+                let position = Position {
+                    offset: position.offset + line_start,
+                    ..position
+                };
+                return Err(Error::LineTooLong { position, section }.into());
+            }
+
+            // what follows a boundary at the edge of `available` could begin another, so
+            // it is left unread, for callers that keep nothing between calls
+            if split && newline.is_none() {
+                cut = scanner
+                    .tentative_end(&buf[line_start..])
+                    .map_or(cut, |end| line_start + end);
+            }
+
+            match newline {
+                _ if cut < buf.len() && cut >= start => {
+                    buf.truncate(cut);
+                    (true, cut - start)
+                }
+                Some(i) => {
+                    buf.push(available[i]);
+                    (true, i + 1)
+                }
+                None => (false, end),
+            }
+        };
+        r.consume(used);
+        read += used;
+        if done || used == 0 {
+            return Ok(read);
+        }
+    }
 }
@@ -0,0 +1,1001 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use core::fmt;
+#[cfg(feature = "std")]
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use pki_types::{
+    CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, PrivateKeyDer,
+    PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
+};
+
+use crate::pemfile::{BorrowedItem, Diagnostics, Error, Item, PemSection, Position, SectionKind};
+#[cfg(feature = "std")]
+use crate::pemfile::{ReadError, ReadState};
+use crate::warning::Warning;
+
+/// Options controlling how PEM is read.
+///
+/// The defaults match the behaviour of [`read_one()`][crate::read_one()] and
+/// [`read_one_from_slice()`][crate::read_one_from_slice()].
+///
+/// By default, parsing is lenient: anything that can be decoded is accepted.  In particular,
+/// base64 with the final `=` padding omitted (as emitted by some load balancers and other
+/// tools) is decoded as if the padding were present.  See [`ParserOptions::strict()`] for
+/// the alternative.
+///
+/// A BEGIN line within a section that is missing its END line (perhaps because it was
+/// truncated) ends that section, which is reported as [`Error::MissingSectionEnd`], and
+/// reading can continue with the new section.
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use rustls_pemfile::{ParserOptions, PemReader};
+///
+/// # let data = b"-----BEGIN BREAKFAST CLUB-----\n-----END BREAKFAST CLUB-----\n";
+/// let options = ParserOptions::new().yield_unknown(true);
+/// let mut reader = PemReader::with_options(&data[..], options);
+/// assert_eq!(reader.next().unwrap().unwrap().label(), "BREAKFAST CLUB");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParserOptions {
+    pub(crate) yield_unknown: bool,
+    pub(crate) strict: bool,
+    pub(crate) ignore_label_case: bool,
+    pub(crate) normalize_markers: bool,
+    pub(crate) reject_text_outside_sections: bool,
+    pub(crate) max_section_bytes: Option<usize>,
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) max_sections: Option<usize>,
+    pub(crate) max_total_decoded: Option<usize>,
+    pub(crate) unescape_newlines: bool,
+    pub(crate) capture_preceding_text: bool,
+    pub(crate) skip_malformed: bool,
+    pub(crate) collect_warnings: bool,
+    pub(crate) redaction: Redaction,
+    #[cfg(feature = "parallel")]
+    pub(crate) decode_in_parallel: bool,
+    /// The kinds of section to read, when the rest are skipped without being decoded.
+    pub(crate) only: Option<&'static [SectionKind]>,
+}
+
+impl ParserOptions {
+    /// Make a new `ParserOptions` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a new `ParserOptions` with limits suitable for reading untrusted input.
+    ///
+    /// Lines are limited to 64 KiB, and section bodies to 1 MiB.  The other settings are
+    /// the defaults.
+    pub fn hardened() -> Self {
+        Self::default()
+            .max_line_length(64 * 1024)
+            .max_section_bytes(1024 * 1024)
+    }
+
+    /// Yield sections with labels this crate does not recognise, as [`Item::Unknown`].
+    ///
+    /// By default these are skipped.
+    pub fn yield_unknown(mut self, yield_unknown: bool) -> Self {
+        self.yield_unknown = yield_unknown;
+        self
+    }
+
+    /// Require input to conform strictly to RFC 7468.
+    ///
+    /// In strict mode, sections with RFC 7468 encapsulation are rejected if their base64
+    /// lines are not all exactly 64 characters (except the last, which may be shorter), if
+    /// they contain whitespace, or if the base64 is not canonically padded.  Any text
+    /// outside sections, other than blank lines, is also rejected, and BEGIN and END lines
+    /// are not recognised unless they are lines of their own.  A BEGIN line within a
+    /// section is an error, giving its line number and the label of the open section.
+    ///
+    /// The default is to accept any input that can be decoded.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Match BEGIN and END lines, and their labels, without regard to ASCII case.
+    ///
+    /// This allows sections such as `-----BEGIN certificate-----` to be read; they are
+    /// yielded as the same [`Item`] variants as their canonical, upper-case, equivalents.
+    ///
+    /// The default is to require labels to match exactly.
+    pub fn ignore_label_case(mut self, ignore: bool) -> Self {
+        self.ignore_label_case = ignore;
+        self
+    }
+
+    /// Accept BEGIN and END lines in which Unicode look-alikes have replaced `-` or ` `.
+    ///
+    /// PEM copied from wikis, PDFs and chat clients often has en or em dashes in place of
+    /// hyphens, or non-breaking spaces in place of spaces.  With this option these are
+    /// replaced before the line is matched.
+    ///
+    /// The default is to require the ASCII characters.
+    pub fn normalize_markers(mut self, normalize: bool) -> Self {
+        self.normalize_markers = normalize;
+        self
+    }
+
+    /// Treat the two-character sequences `\n` and `\r` as line breaks.
+    ///
+    /// PEM stored in environment variables, JSON strings and similar often has its line
+    /// breaks escaped like this.  With this option it can be read without first being
+    /// unescaped.  Note that a [`PemReader`] still buffers input up to a real line break,
+    /// which may be the whole input, when applying [`ParserOptions::max_line_length()`].
+    ///
+    /// The default is to read such sequences literally.
+    pub fn unescape_newlines(mut self, unescape: bool) -> Self {
+        self.unescape_newlines = unescape;
+        self
+    }
+
+    /// Capture the text preceding each section, as [`PemSection::preceding_text`].
+    ///
+    /// Bundles such as curl's `cacert.pem` precede each certificate with its name; this
+    /// allows tools to preserve or display such annotations.  Text preceding a section that
+    /// is skipped is discarded.  Captured text counts towards
+    /// [`ParserOptions::max_section_bytes()`].
+    ///
+    /// The default is to discard such text.
+    pub fn capture_preceding_text(mut self, capture: bool) -> Self {
+        self.capture_preceding_text = capture;
+        self
+    }
+
+    /// Skip malformed sections, rather than stopping at the first.
+    ///
+    /// Without this option, one corrupt section in a bundle means none of the sections after
+    /// it can be read.  With it, a section that cannot be read is skipped, as is any
+    /// malformed text outside sections, and reading continues with the rest of the input.
+    /// The errors are available from [`PemReader::errors()`]; see also
+    /// [`ParserOptions::read_all_from_slice()`].
+    ///
+    /// Errors from the underlying reader, and from exceeding the limits set by these options,
+    /// still stop reading.
+    pub fn skip_malformed(mut self, skip: bool) -> Self {
+        self.skip_malformed = skip;
+        self
+    }
+
+    /// Collect warnings about anomalies in the input that do not prevent it being read.
+    ///
+    /// These are available from [`PemReader::warnings()`], and include irregular base64
+    /// line lengths, trailing whitespace, unrecognised or lower-case labels, and duplicate
+    /// certificates: see [`Warning`][crate::Warning].
+    ///
+    /// The default is not to collect warnings.
+    pub fn collect_warnings(mut self, collect: bool) -> Self {
+        self.collect_warnings = collect;
+        self
+    }
+
+    /// Reject any text, other than whitespace, outside sections.
+    ///
+    /// By default such text is ignored, which allows explanatory text between sections but
+    /// can hide truncation or corruption.  With this option an error giving the byte offset
+    /// of the text is returned instead.  [`ParserOptions::strict()`] implies this.
+    pub fn reject_text_outside_sections(mut self, reject: bool) -> Self {
+        self.reject_text_outside_sections = reject;
+        self
+    }
+
+    /// Limit the size of each section's body to `max` bytes.
+    ///
+    /// The body is the encoded text between the BEGIN and END lines, including any headers
+    /// and line endings.  Reading stops with an error as soon as it exceeds the limit, so this
+    /// bounds the memory used when reading untrusted input.
+    ///
+    /// The default is no limit.
+    pub fn max_section_bytes(mut self, max: usize) -> Self {
+        self.max_section_bytes = Some(max);
+        self
+    }
+
+    /// Limit the length of each line to `max` bytes, excluding the line ending.
+    ///
+    /// A line is buffered in full before it is parsed, so without a limit a long enough
+    /// line with no line ending can exhaust memory.  Reading stops with an error as soon as
+    /// a line exceeds the limit.
+    ///
+    /// The default is no limit; [`ParserOptions::hardened()`] sets a limit of 64 KiB.
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = Some(max);
+        self
+    }
+
+    /// Limit the number of sections read to `max`.
+    ///
+    /// Every section counts towards the limit, including those with labels that are not
+    /// recognised, whether or not [`ParserOptions::yield_unknown()`] is set.  Once it is
+    /// exceeded, reading stops with an error.  The count is kept by a [`PemReader`], so the
+    /// limit applies to the whole of its input, as it does to the input of
+    /// [`ParserOptions::read_all_borrowed()`] and `ParserOptions::read_all_parallel()`;
+    /// [`ParserOptions::read_one_from_slice()`] reads only one item, so is not limited.
+    ///
+    /// The default is no limit.
+    pub fn max_sections(mut self, max: usize) -> Self {
+        self.max_sections = Some(max);
+        self
+    }
+
+    /// Limit the total size of the decoded contents of all sections read to `max` bytes.
+    ///
+    /// This complements [`ParserOptions::max_section_bytes()`], bounding the output of a
+    /// file with many sections each within that limit.  As for
+    /// [`ParserOptions::max_sections()`], the total is kept by a [`PemReader`], and every
+    /// section read counts towards it.
+    ///
+    /// The default is no limit.
+    pub fn max_total_decoded(mut self, max: usize) -> Self {
+        self.max_total_decoded = Some(max);
+        self
+    }
+
+    /// Choose which errors have content from the input removed, so they can be logged.
+    ///
+    /// The line of an [`Error::IllegalSectionStart`] and the character of an invalid
+    /// character in an [`Error::Base64Decode`] are replaced by their length and position.
+    ///
+    /// The default is [`Redaction::Secrets`].
+    pub fn redact(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Decode the bodies of large sections across the [rayon] thread pool.
+    ///
+    /// This is for sections of many megabytes, such as the CRLs of large certificate
+    /// authorities, which would otherwise be decoded on one core.  Each section's body is
+    /// held until its END line, which takes as much memory again as the body, and bodies of
+    /// a megabyte or more are then decoded in chunks.  The results, and any errors, are as
+    /// without this option.
+    ///
+    /// The default is to decode bodies in order as they are read.
+    #[cfg(feature = "parallel")]
+    pub fn decode_in_parallel(mut self, parallel: bool) -> Self {
+        self.decode_in_parallel = parallel;
+        self
+    }
+
+    /// Extract and decode the next PEM section from `input`, using these options.
+    ///
+    /// The result is as for [`read_one_from_slice()`][crate::read_one_from_slice()].
+    pub fn read_one_from_slice<'a>(
+        &self,
+        input: &'a [u8],
+    ) -> Result<Option<(Item, &'a [u8])>, Error> {
+        Item::from_slice(input, self)
+    }
+
+    /// Extract and decode every PEM section in `input`, using these options, decoding
+    /// their contents into `buf`.
+    ///
+    /// The result is as for [`read_all_borrowed()`][crate::read_all_borrowed()].  Reading
+    /// stops at the first malformed section, whether or not
+    /// [`ParserOptions::skip_malformed()`] is set.  The limits set by
+    /// [`ParserOptions::max_sections()`] and [`ParserOptions::max_total_decoded()`] apply to
+    /// the whole of `input`.
+    pub fn read_all_borrowed<'b>(
+        &self,
+        input: &[u8],
+        buf: &'b mut Vec<u8>,
+    ) -> Result<Vec<BorrowedItem<'b>>, Error> {
+        BorrowedItem::from_slice_into(input, self, buf)
+    }
+
+    /// Extract and decode every PEM section in `input`, using these options, decoding
+    /// them in parallel.
+    ///
+    /// The result is as for [`read_all_parallel()`][crate::read_all_parallel()].  Reading
+    /// stops at the first malformed section, whether or not
+    /// [`ParserOptions::skip_malformed()`] is set.  The limits set by
+    /// [`ParserOptions::max_sections()`] and [`ParserOptions::max_total_decoded()`] apply to
+    /// the whole of `input`.
+    #[cfg(feature = "parallel")]
+    pub fn read_all_parallel(&self, input: &[u8]) -> Result<Vec<Item>, Error> {
+        crate::parallel::read_all(input, self)
+    }
+
+    /// Extract and decode the next PEM section from `input`, using these options, with
+    /// its headers.
+    ///
+    /// The result is otherwise as for [`ParserOptions::read_one_from_slice()`].
+    pub fn read_section_from_slice<'a>(
+        &self,
+        input: &'a [u8],
+    ) -> Result<Option<(PemSection, &'a [u8])>, Error> {
+        PemSection::from_slice(
+            input,
+            &mut Position::default(),
+            self,
+            &mut Diagnostics::default(),
+        )
+    }
+
+    /// Read every PEM section in `input`, using these options, collecting all the errors
+    /// rather than stopping at the first.
+    ///
+    /// This is for reporting every problem in an input at once.  Malformed sections are
+    /// skipped, as with [`ParserOptions::skip_malformed()`], whether or not that is set.
+    /// An error that prevents reading any further, such as exceeding a limit, is the last
+    /// in [`Report::errors`].  Warnings are collected if
+    /// [`ParserOptions::collect_warnings()`] is set.
+    pub fn read_all_from_slice(&self, mut input: &[u8]) -> Report {
+        let options = self.clone().skip_malformed(true);
+        let mut position = Position::default();
+        let mut diagnostics = Diagnostics::default();
+        let mut items = Vec::new();
+
+        loop {
+            match PemSection::from_slice(input, &mut position, &options, &mut diagnostics) {
+                Ok(Some((section, rest))) => {
+                    items.push(section.item);
+                    input = rest;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    diagnostics.errors.push(err);
+                    break;
+                }
+            }
+        }
+
+        Report {
+            items,
+            errors: diagnostics.errors,
+            warnings: diagnostics.warnings,
+        }
+    }
+}
+
+/// Which errors have content from the input removed: see [`ParserOptions::redact()`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Redaction {
+    /// Redact errors in sections whose label indicates secret material, such as
+    /// "PRIVATE KEY", or that is not recognised.
+    #[default]
+    Secrets,
+    /// Redact every error.
+    Always,
+    /// Never redact errors.
+    Never,
+}
+
+/// The result of [`ParserOptions::read_all_from_slice()`].
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    /// The items read, in order.
+    pub items: Vec<Item>,
+    /// The errors found, in order.
+    pub errors: Vec<Error>,
+    /// The warnings found, when [`ParserOptions::collect_warnings()`] is set.
+    pub warnings: Vec<Warning>,
+}
+
+/// A parser of PEM sections, which keeps its buffers from one section to the next.
+///
+/// This does not own its input: each call is passed the reader to continue from, which
+/// must be the same until [`Parser::reset()`] is called.  Input that was read from it but
+/// not yet parsed is kept by the parser.  Reusing one `Parser` for many sections, or for
+/// many inputs, avoids allocating new line buffers for each.
+///
+/// ```
+/// use rustls_pemfile::{Item, Parser};
+///
+/// let mut parser = Parser::new();
+/// for pem in [&include_bytes!("../tests/data/certificate.chain.pem")[..]] {
+///     let mut rd = pem;
+///     while let Some(item) = parser.next_item(&mut rd).unwrap() {
+///         assert!(matches!(item, Item::X509Certificate(_)));
+///     }
+///     parser.reset();
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct Parser {
+    options: ParserOptions,
+    state: ReadState,
+}
+
+#[cfg(feature = "std")]
+impl Parser {
+    /// Make a new `Parser` with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a new `Parser` with the given options.
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            options,
+            state: ReadState::default(),
+        }
+    }
+
+    /// Extract and decode the next PEM section from `rd`.
+    ///
+    /// The result is as for [`read_one()`][crate::read_one()].
+    pub fn next_item<R: io::BufRead + ?Sized>(
+        &mut self,
+        rd: &mut R,
+    ) -> Result<Option<Item>, io::Error> {
+        Item::from_buf(rd, &self.options, &mut self.state)
+    }
+
+    /// Extract and decode the next PEM section from `rd`, with its headers.
+    ///
+    /// The result is otherwise as for [`Parser::next_item()`].
+    pub fn next_section<R: io::BufRead + ?Sized>(
+        &mut self,
+        rd: &mut R,
+    ) -> Result<Option<PemSection>, io::Error> {
+        PemSection::from_buf(rd, &self.options, &mut self.state)
+    }
+
+    /// Start parsing another input, keeping the buffers.
+    ///
+    /// Any input that was read but not yet parsed is discarded, as are the errors and
+    /// warnings, and the counts kept for [`ParserOptions::max_sections()`] and
+    /// [`ParserOptions::max_total_decoded()`].
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    /// The errors in the sections skipped so far, when
+    /// [`ParserOptions::skip_malformed()`] is set.
+    pub fn errors(&self) -> &[Error] {
+        &self.state.diagnostics.errors
+    }
+
+    /// The warnings about the input read so far, when
+    /// [`ParserOptions::collect_warnings()`] is set.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.state.diagnostics.warnings
+    }
+
+    /// The options this parser was made with.
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+}
+
+/// A reader of PEM sections from an underlying [`io::BufRead`], with [`ParserOptions`].
+///
+/// This is an iterator over the items read.
+///
+/// Input that starts with a UTF-16 byte order mark, as written by some Windows tools, is
+/// transcoded to UTF-8 before it is parsed.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PemReader<R> {
+    rd: R,
+    parser: Parser,
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead> PemReader<R> {
+    /// Make a new `PemReader` reading from `rd`, with the default options.
+    pub fn new(rd: R) -> Self {
+        Self::with_options(rd, ParserOptions::default())
+    }
+
+    /// Make a new `PemReader` reading from `rd`, with the given options.
+    pub fn with_options(rd: R, options: ParserOptions) -> Self {
+        Self {
+            rd,
+            parser: Parser::with_options(options),
+        }
+    }
+
+    /// Extract and decode the next PEM section.
+    ///
+    /// The result is as for [`read_one()`][crate::read_one()].
+    pub fn read_one(&mut self) -> Result<Option<Item>, io::Error> {
+        self.parser.next_item(&mut self.rd)
+    }
+
+    /// Extract and decode the next PEM section, with its headers.
+    ///
+    /// The result is otherwise as for [`PemReader::read_one()`].
+    pub fn read_section(&mut self) -> Result<Option<PemSection>, io::Error> {
+        self.parser.next_section(&mut self.rd)
+    }
+
+    /// The errors in the sections skipped so far, when
+    /// [`ParserOptions::skip_malformed()`] is set.
+    pub fn errors(&self) -> &[Error] {
+        self.parser.errors()
+    }
+
+    /// The warnings about the input read so far, when
+    /// [`ParserOptions::collect_warnings()`] is set.
+    pub fn warnings(&self) -> &[Warning] {
+        self.parser.warnings()
+    }
+
+    /// The options this reader was made with.
+    pub fn options(&self) -> &ParserOptions {
+        self.parser.options()
+    }
+
+    /// Recover the underlying reader.
+    ///
+    /// Any input that was read from it but not yet parsed is lost.
+    pub fn into_inner(self) -> R {
+        self.rd
+    }
+
+    /// Read only sections of the given `kinds`, skipping the rest without decoding them.
+    fn only(mut self, kinds: &'static [SectionKind]) -> Self {
+        self.parser.options.only = Some(kinds);
+        self
+    }
+
+    /// Return an iterator over the certificates read.
+    ///
+    /// This is as for [`certs()`][crate::certs()], but reading with this reader's options,
+    /// and without dispatching dynamically to the underlying reader.
+    pub fn certs(self) -> KindIter<R, CertificateDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::Certificate]))
+    }
+
+    /// Return the first private key read.
+    ///
+    /// This is as for [`private_key()`][crate::private_key()], but reading with this
+    /// reader's options, and without dispatching dynamically to the underlying reader.
+    pub fn private_key(self) -> Result<Option<PrivateKeyDer<'static>>, ReadError> {
+        self.private_keys().next().transpose()
+    }
+
+    /// Return an iterator over the private keys read, of any of the kinds that
+    /// [`PemReader::private_key()`] returns.
+    ///
+    /// This is as for [`private_keys()`][crate::private_keys()], but reading with this
+    /// reader's options, and without dispatching dynamically to the underlying reader.
+    pub fn private_keys(self) -> KindIter<R, PrivateKeyDer<'static>> {
+        let keys = &[
+            SectionKind::RsaPrivateKey,
+            SectionKind::PrivateKey,
+            SectionKind::EcPrivateKey,
+        ];
+        KindIter::new(self.only(keys))
+    }
+
+    /// Return the certificates read, and the first private key.
+    ///
+    /// This is as for [`identity()`][crate::identity()], but reading with this reader's
+    /// options, and without dispatching dynamically to the underlying reader.
+    pub fn identity(
+        self,
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ReadError> {
+        let kinds = &[
+            SectionKind::Certificate,
+            SectionKind::RsaPrivateKey,
+            SectionKind::PrivateKey,
+            SectionKind::EcPrivateKey,
+        ];
+        let (mut certs, mut key) = (Vec::new(), None);
+        for item in self.only(kinds) {
+            match item? {
+                Item::X509Certificate(cert) => certs.push(cert),
+                Item::Pkcs1Key(k) if key.is_none() => key = Some(k.into()),
+                Item::Pkcs8Key(k) if key.is_none() => key = Some(k.into()),
+                Item::Sec1Key(k) if key.is_none() => key = Some(k.into()),
+                _ => {}
+            }
+        }
+
+        match key {
+            Some(key) => Ok((certs, key)),
+            None => Err(ReadError::NotFound {
+                expected: "private key",
+            }),
+        }
+    }
+
+    /// Return the certificates read, and the only private key.
+    ///
+    /// This is as for [`identity_strict()`][crate::identity_strict()], but reading with this
+    /// reader's options, and without dispatching dynamically to the underlying reader.
+    pub fn identity_strict(
+        self,
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ReadError> {
+        let kinds = &[
+            SectionKind::Certificate,
+            SectionKind::RsaPrivateKey,
+            SectionKind::PrivateKey,
+            SectionKind::EcPrivateKey,
+        ];
+        let mut reader = self.only(kinds);
+        let (mut certs, mut keys) = (Vec::new(), Vec::new());
+        while let Some(section) = reader.read_section()? {
+            let line = section.span.start.line;
+            match section.item {
+                Item::X509Certificate(cert) => certs.push(cert),
+                Item::Pkcs1Key(key) => keys.push((PrivateKeyDer::from(key), line)),
+                Item::Pkcs8Key(key) => keys.push((PrivateKeyDer::from(key), line)),
+                Item::Sec1Key(key) => keys.push((PrivateKeyDer::from(key), line)),
+                _ => {}
+            }
+        }
+
+        let mut keys = keys.into_iter();
+        let (key, first) = keys.next().ok_or(ReadError::NotFound {
+            expected: "private key",
+        })?;
+        let extra = keys.map(|(_, line)| line).collect::<Vec<_>>();
+        if !extra.is_empty() {
+            return Err(ReadError::MultipleKeys { first, extra });
+        }
+
+        match certs.is_empty() {
+            true => Err(ReadError::NotFound {
+                expected: "certificate",
+            }),
+            false => Ok((certs, key)),
+        }
+    }
+
+    /// Return an iterator over the items read whose labels `predicate` accepts.
+    ///
+    /// This is as for [`sections_matching()`][crate::sections_matching()], but reading with
+    /// this reader's options, and without dispatching dynamically to the underlying reader.
+    /// Sections with labels that are not recognised are passed to `predicate` whether or not
+    /// [`ParserOptions::yield_unknown()`] is set.
+    pub fn sections_matching<F: FnMut(&str) -> bool>(
+        mut self,
+        predicate: F,
+    ) -> SectionsMatching<R, F> {
+        self.parser.options.yield_unknown = true;
+        SectionsMatching {
+            reader: self,
+            predicate,
+        }
+    }
+
+    /// Return the first certificate signing request (CSR) read.
+    ///
+    /// This is as for [`csr()`][crate::csr()], but reading with this reader's options, and
+    /// without dispatching dynamically to the underlying reader.
+    pub fn csr(self) -> Result<Option<CertificateSigningRequestDer<'static>>, ReadError> {
+        self.csrs().next().transpose()
+    }
+
+    /// Return an iterator over the certificate signing requests (CSRs) read.
+    ///
+    /// This is as for [`csrs()`][crate::csrs()], but reading with this reader's options,
+    /// and without dispatching dynamically to the underlying reader.
+    pub fn csrs(self) -> KindIter<R, CertificateSigningRequestDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::Csr]))
+    }
+
+    /// Return an iterator over the certificate revocation lists (CRLs) read.
+    ///
+    /// This is as for [`crls()`][crate::crls()], but reading with this reader's options,
+    /// and without dispatching dynamically to the underlying reader.
+    pub fn crls(self) -> KindIter<R, CertificateRevocationListDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::Crl]))
+    }
+
+    /// Return an iterator over the RSA private keys read.
+    ///
+    /// This is as for [`rsa_private_keys()`][crate::rsa_private_keys()], but reading with
+    /// this reader's options, and without dispatching dynamically to the underlying reader.
+    pub fn rsa_private_keys(self) -> KindIter<R, PrivatePkcs1KeyDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::RsaPrivateKey]))
+    }
+
+    /// Return an iterator over the PKCS8-encoded private keys read.
+    ///
+    /// This is as for [`pkcs8_private_keys()`][crate::pkcs8_private_keys()], but reading
+    /// with this reader's options, and without dispatching dynamically to the underlying
+    /// reader.
+    pub fn pkcs8_private_keys(self) -> KindIter<R, PrivatePkcs8KeyDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::PrivateKey]))
+    }
+
+    /// Return an iterator over the SEC1-encoded EC private keys read.
+    ///
+    /// This is as for [`ec_private_keys()`][crate::ec_private_keys()], but reading with
+    /// this reader's options, and without dispatching dynamically to the underlying reader.
+    pub fn ec_private_keys(self) -> KindIter<R, PrivateSec1KeyDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::EcPrivateKey]))
+    }
+
+    /// Return an iterator over the SPKI-encoded public keys read.
+    ///
+    /// This is as for [`public_keys()`][crate::public_keys()], but reading with this
+    /// reader's options, and without dispatching dynamically to the underlying reader.
+    pub fn public_keys(self) -> KindIter<R, SubjectPublicKeyInfoDer<'static>> {
+        KindIter::new(self.only(&[SectionKind::PublicKey]))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead> Iterator for PemReader<R> {
+    type Item = Result<Item, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_one().transpose()
+    }
+}
+
+/// An iterator over the sections of one kind read by a [`PemReader`], such as the
+/// certificates returned by [`PemReader::certs()`].
+///
+/// Other sections are skipped without being decoded.  The aliases of this type, such as
+/// [`CertIter`], are what the helper functions like [`certs()`][crate::certs()] return.
+#[cfg(feature = "std")]
+pub struct KindIter<R, T> {
+    reader: PemReader<R>,
+    item: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead, T> KindIter<R, T> {
+    fn new(reader: PemReader<R>) -> Self {
+        Self {
+            reader,
+            item: PhantomData,
+        }
+    }
+
+    /// Recover the underlying reader.
+    ///
+    /// Any input that was read from it but not yet parsed is lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead, T: TryFrom<Item>> Iterator for KindIter<R, T> {
+    type Item = Result<T, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.next()? {
+                Ok(item) => match T::try_from(item) {
+                    Ok(item) => return Some(Ok(item)),
+                    Err(_) => continue,
+                },
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: fmt::Debug, T> fmt::Debug for KindIter<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KindIter")
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
+
+/// An iterator over the items read by a [`PemReader`] whose labels satisfy a predicate:
+/// see [`PemReader::sections_matching()`].
+#[cfg(feature = "std")]
+pub struct SectionsMatching<R, F> {
+    reader: PemReader<R>,
+    predicate: F,
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead, F: FnMut(&str) -> bool> Iterator for SectionsMatching<R, F> {
+    type Item = Result<Item, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.next()? {
+                Ok(item) if (self.predicate)(&item.label()) => return Some(Ok(item)),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: fmt::Debug, F> fmt::Debug for SectionsMatching<R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SectionsMatching")
+            .field("reader", &self.reader)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The iterator returned by [`certs()`][crate::certs()].
+#[cfg(feature = "std")]
+pub type CertIter<'a> = KindIter<&'a mut dyn io::BufRead, CertificateDer<'static>>;
+
+/// The iterator returned by [`crls()`][crate::crls()].
+#[cfg(feature = "std")]
+pub type CrlIter<'a> = KindIter<&'a mut dyn io::BufRead, CertificateRevocationListDer<'static>>;
+
+/// The iterator returned by [`csrs()`][crate::csrs()].
+#[cfg(feature = "std")]
+pub type CsrIter<'a> = KindIter<&'a mut dyn io::BufRead, CertificateSigningRequestDer<'static>>;
+
+/// The iterator returned by [`private_keys()`][crate::private_keys()].
+#[cfg(feature = "std")]
+pub type PrivateKeyIter<'a> = KindIter<&'a mut dyn io::BufRead, PrivateKeyDer<'static>>;
+
+/// The iterator returned by [`rsa_private_keys()`][crate::rsa_private_keys()].
+#[cfg(feature = "std")]
+pub type RsaPrivateKeyIter<'a> = KindIter<&'a mut dyn io::BufRead, PrivatePkcs1KeyDer<'static>>;
+
+/// The iterator returned by [`pkcs8_private_keys()`][crate::pkcs8_private_keys()].
+#[cfg(feature = "std")]
+pub type Pkcs8PrivateKeyIter<'a> = KindIter<&'a mut dyn io::BufRead, PrivatePkcs8KeyDer<'static>>;
+
+/// The iterator returned by [`ec_private_keys()`][crate::ec_private_keys()].
+#[cfg(feature = "std")]
+pub type EcPrivateKeyIter<'a> = KindIter<&'a mut dyn io::BufRead, PrivateSec1KeyDer<'static>>;
+
+/// The iterator returned by [`public_keys()`][crate::public_keys()].
+#[cfg(feature = "std")]
+pub type PublicKeyIter<'a> = KindIter<&'a mut dyn io::BufRead, SubjectPublicKeyInfoDer<'static>>;
+
+/// A reader of PEM sections from a slice, with [`ParserOptions`].
+///
+/// This is an iterator over the items read, from either end.  Reading from the back scans
+/// backwards for the BEGIN line of the last section, so that the last item, such as the
+/// root at the end of a certificate chain, is read without decoding those before it.
+///
+/// The positions in errors are relative to the start of the slice.  Reading stops at the
+/// first error, from either end, unless [`ParserOptions::skip_malformed()`] is set.
+///
+/// ```
+/// use rustls_pemfile::{Item, SliceReader};
+///
+/// let chain = include_bytes!("../tests/data/certificate.chain.pem");
+/// let root = SliceReader::new(chain).next_back().unwrap().unwrap();
+/// assert!(matches!(root, Item::X509Certificate(_)));
+/// ```
+#[derive(Debug)]
+pub struct SliceReader<'a> {
+    /// The input not yet read from either end.
+    input: &'a [u8],
+    /// Where `input` starts.
+    position: Position,
+    options: ParserOptions,
+    diagnostics: Diagnostics,
+}
+
+impl<'a> SliceReader<'a> {
+    /// Make a new `SliceReader` reading from `input`, with the default options.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    /// Make a new `SliceReader` reading from `input`, with the given options.
+    pub fn with_options(input: &'a [u8], options: ParserOptions) -> Self {
+        Self {
+            input,
+            position: Position::default(),
+            options,
+            diagnostics: Diagnostics::default(),
+        }
+    }
+
+    /// The input not yet read, from either end.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// The errors in the sections skipped so far, when
+    /// [`ParserOptions::skip_malformed()`] is set.
+    pub fn errors(&self) -> &[Error] {
+        &self.diagnostics.errors
+    }
+
+    /// The warnings about the input read so far, when
+    /// [`ParserOptions::collect_warnings()`] is set.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.diagnostics.warnings
+    }
+
+    /// The options this reader was made with.
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+}
+
+impl Iterator for SliceReader<'_> {
+    type Item = Result<Item, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let read = PemSection::from_slice(
+            self.input,
+            &mut self.position,
+            &self.options,
+            &mut self.diagnostics,
+        );
+        match read {
+            Ok(Some((section, rest))) => {
+                self.input = rest;
+                Some(Ok(section.item))
+            }
+            Ok(None) => {
+                self.input = &[];
+                None
+            }
+            Err(err) => {
+                self.input = &[];
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for SliceReader<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(start) = last_begin(self.input) {
+            let (before, rest) = self.input.split_at(start);
+            let mut position = Position {
+                offset: self.position.offset + start,
+                line: self.position.line + before.iter().filter(|&&b| b == b'\n').count(),
+            };
+            let read =
+                PemSection::from_slice(rest, &mut position, &self.options, &mut self.diagnostics);
+            self.input = before;
+            match read {
+                Ok(Some((section, _))) => return Some(Ok(section.item)),
+                Ok(None) => {}
+                Err(err) => {
+                    self.input = &[];
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        // what is left has no BEGIN line, but may still be malformed
+        self.next()
+    }
+}
+
+/// Where the last line in `input` that could be a BEGIN line starts.
+fn last_begin(input: &[u8]) -> Option<usize> {
+    let mut end = input.len();
+    loop {
+        let start = input[..end]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |index| index + 1);
+        if may_begin(&input[start..end]) {
+            return Some(start);
+        }
+
+        match start {
+            0 => return None,
+            _ => end = start - 1,
+        }
+    }
+}
+
+/// Whether `line` could be a BEGIN line.
+///
+/// This is any line containing `BEGIN` that does not start with a base64 character, so
+/// may turn out not to be one after all.
+fn may_begin(line: &[u8]) -> bool {
+    let line = line.trim_ascii_start();
+    let base64 = line
+        .first()
+        .is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/'));
+    !base64 && line.windows(5).any(|w| w.eq_ignore_ascii_case(b"BEGIN"))
+}
@@ -0,0 +1,138 @@
+use core::ops::Range;
+use std::io;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::pemfile::trim_end;
+
+/// Where a PEM section is in its input, as found by [`scan()`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionInfo {
+    /// The section's label, such as "CERTIFICATE".
+    pub label: String,
+    /// The lines from the section's BEGIN line to its END line, numbered from 1.
+    pub line_range: Range<usize>,
+    /// The byte offsets from the start of the section's BEGIN line to the end of its END
+    /// line, including the END line's line ending.
+    pub byte_range: Range<usize>,
+    /// An estimate of the size of the section's decoded contents, from the length of its
+    /// body.
+    pub approx_len: usize,
+}
+
+/// List the PEM sections in `rd`, without decoding them.
+///
+/// This only looks for each section's BEGIN and END lines, so costs much less than
+/// [`read_all()`][crate::read_all()] for large inputs.  As the bodies are not decoded,
+/// a section listed here may still fail to be read.  A section with no END line is not
+/// listed.
+pub fn scan(rd: &mut dyn io::BufRead) -> Result<Vec<SectionInfo>, io::Error> {
+    let mut sections = Vec::new();
+    let mut open: Option<(Vec<u8>, usize, usize, usize)> = None;
+    let (mut line_number, mut offset) = (1, 0);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = rd.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+
+        let start = line
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(line.len());
+        let trimmed = trim_end(&line[start..]);
+        if let Some(label) = marker(trimmed, b"-----BEGIN ") {
+            open = Some((label.to_vec(), line_number, offset, 0));
+        } else if let Some((label, first_line, first_offset, body)) = &mut open {
+            match marker(trimmed, b"-----END ") {
+                Some(end) if end == &label[..] => {
+                    sections.push(SectionInfo {
+                        label: String::from_utf8_lossy(label).into_owned(),
+                        line_range: *first_line..line_number + 1,
+                        byte_range: *first_offset..offset + read,
+                        approx_len: *body / 4 * 3,
+                    });
+                    open = None;
+                }
+                _ => *body += trimmed.iter().filter(|b| !b.is_ascii_whitespace()).count(),
+            }
+        }
+
+        line_number += 1;
+        offset += read;
+    }
+
+    Ok(sections)
+}
+
+/// Pass the text of each PEM section in `rd` to `sink`, with its label, without decoding
+/// it; returning how many sections there were.
+///
+/// This is for splitting a combined file into its parts, such as certificates into one
+/// file and keys into another.  Each section's text is passed on exactly as read, from its
+/// BEGIN line to its END line, with any headers and line endings.  Text outside sections
+/// is dropped, as is a section with no END line.  As for [`scan()`], the label is as read,
+/// not canonicalised, and the bodies are not checked.  An error from `sink` stops the split
+/// and is returned.
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut data = &include_bytes!("../tests/data/zen.pem")[..];
+/// let (mut certs, mut keys) = (Vec::new(), Vec::new());
+/// rustls_pemfile::split_sections(&mut data, |label, text| match label {
+///     "CERTIFICATE" => certs.write_all(text),
+///     label if label.ends_with("PRIVATE KEY") => keys.write_all(text),
+///     _ => Ok(()),
+/// })
+/// .unwrap();
+/// assert_eq!(rustls_pemfile::certs(&mut &certs[..]).count(), 4);
+/// assert_eq!(rustls_pemfile::private_keys(&mut &keys[..]).count(), 4);
+/// ```
+pub fn split_sections(
+    rd: &mut dyn io::BufRead,
+    mut sink: impl FnMut(&str, &[u8]) -> Result<(), io::Error>,
+) -> Result<usize, io::Error> {
+    let (mut open, mut text) = (None::<Vec<u8>>, Vec::new());
+    let mut sections = 0;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if rd.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+
+        let start = line
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(line.len());
+        let trimmed = trim_end(&line[start..]);
+        if let Some(label) = marker(trimmed, b"-----BEGIN ") {
+            open = Some(label.to_vec());
+            text.clear();
+        }
+
+        let label = match &open {
+            Some(label) => label,
+            None => continue,
+        };
+        text.extend_from_slice(&line);
+        if marker(trimmed, b"-----END ") == Some(&label[..]) {
+            sink(&String::from_utf8_lossy(label), &text)?;
+            sections += 1;
+            open = None;
+        }
+    }
+
+    Ok(sections)
+}
+
+/// The label of `line`, if it is an encapsulation boundary starting with `prefix`.
+fn marker<'a>(line: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    line.strip_prefix(prefix)?.strip_suffix(b"-----")
+}
@@ -0,0 +1,35 @@
+use core::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::pemfile::Item;
+
+/// Serialized as a string of its PEM text, as returned by [`Item::to_pem_string()`].
+impl Serialize for Item {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_pem_string())
+    }
+}
+
+/// Deserialized from a string of PEM text holding exactly one section, as for the `FromStr`
+/// implementation.
+impl<'de> Deserialize<'de> for Item {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(ItemVisitor)
+    }
+}
+
+struct ItemVisitor;
+
+impl<'de> Visitor<'de> for ItemVisitor {
+    type Value = Item;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string holding one PEM section")
+    }
+
+    fn visit_str<E: de::Error>(self, pem: &str) -> Result<Self::Value, E> {
+        pem.parse().map_err(E::custom)
+    }
+}
@@ -0,0 +1,184 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use pki_types::CertificateDer;
+
+/// OpenSSL's auxiliary trust settings for a certificate.
+///
+/// These are appended to the certificate in "TRUSTED CERTIFICATE" sections, as
+/// written by `openssl x509 -trustout`.
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CertificateTrust {
+    /// The purposes the certificate is trusted for.
+    ///
+    /// Each entry is the contents octets of an OBJECT IDENTIFIER; for example
+    /// `2b 06 01 05 05 07 03 01` for id-kp-serverAuth.
+    pub trusted: Vec<Vec<u8>>,
+
+    /// The purposes the certificate is explicitly distrusted for.
+    ///
+    /// Each entry is encoded as for `trusted`.
+    pub rejected: Vec<Vec<u8>>,
+
+    /// A friendly name for the certificate, if one was set.
+    pub alias: Option<String>,
+
+    /// A key identifier for the certificate, if one was set.
+    pub key_id: Option<Vec<u8>>,
+}
+
+/// Split the contents of a "TRUSTED CERTIFICATE" section into the certificate and
+/// its trust settings.
+///
+/// Returns `None` if the contents are not well-formed, or if the trust settings
+/// include the `other` field: OpenSSL never sets it, and `CertificateTrust` has
+/// nowhere to keep it.
+pub(crate) fn split(data: &[u8]) -> Option<(CertificateDer<'static>, CertificateTrust)> {
+    let mut input = data;
+    read_tlv(&mut input, SEQUENCE)?;
+    let cert = CertificateDer::from(data[..data.len() - input.len()].to_vec());
+
+    if input.is_empty() {
+        return Some((cert, CertificateTrust::default()));
+    }
+
+    // X509_CERT_AUX ::= SEQUENCE {
+    //     trust       SEQUENCE OF OBJECT IDENTIFIER OPTIONAL,
+    //     reject  [0] IMPLICIT SEQUENCE OF OBJECT IDENTIFIER OPTIONAL,
+    //     alias       UTF8String OPTIONAL,
+    //     keyid       OCTET STRING OPTIONAL,
+    //     other   [1] IMPLICIT SEQUENCE OF AlgorithmIdentifier OPTIONAL
+    // }
+    let mut aux = read_tlv(&mut input, SEQUENCE)?;
+    if !input.is_empty() {
+        return None;
+    }
+
+    let mut trust = CertificateTrust::default();
+    if let Some(oids) = read_optional(&mut aux, SEQUENCE)? {
+        trust.trusted = read_oids(oids)?;
+    }
+    if let Some(oids) = read_optional(&mut aux, CONTEXT_CONSTRUCTED_0)? {
+        trust.rejected = read_oids(oids)?;
+    }
+    if let Some(alias) = read_optional(&mut aux, UTF8_STRING)? {
+        trust.alias = Some(String::from_utf8(alias.to_vec()).ok()?);
+    }
+    if let Some(key_id) = read_optional(&mut aux, OCTET_STRING)? {
+        trust.key_id = Some(key_id.to_vec());
+    }
+
+    // `other` (tagged `CONTEXT_CONSTRUCTED_1`) is left in `aux`, so it is rejected
+    match aux.is_empty() {
+        true => Some((cert, trust)),
+        false => None,
+    }
+}
+
+/// The inverse of `split`: the contents of a "TRUSTED CERTIFICATE" section.
+pub(crate) fn join(cert: &CertificateDer<'_>, trust: &CertificateTrust) -> Vec<u8> {
+    let mut out = cert.to_vec();
+    if *trust == CertificateTrust::default() {
+        return out;
+    }
+
+    let mut aux = Vec::new();
+    if !trust.trusted.is_empty() {
+        write_tlv(&mut aux, SEQUENCE, &write_oids(&trust.trusted));
+    }
+    if !trust.rejected.is_empty() {
+        write_tlv(
+            &mut aux,
+            CONTEXT_CONSTRUCTED_0,
+            &write_oids(&trust.rejected),
+        );
+    }
+    if let Some(alias) = &trust.alias {
+        write_tlv(&mut aux, UTF8_STRING, alias.as_bytes());
+    }
+    if let Some(key_id) = &trust.key_id {
+        write_tlv(&mut aux, OCTET_STRING, key_id);
+    }
+
+    write_tlv(&mut out, SEQUENCE, &aux);
+    out
+}
+
+fn write_oids(oids: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for oid in oids {
+        write_tlv(&mut out, OBJECT_IDENTIFIER, oid);
+    }
+    out
+}
+
+pub(crate) fn write_tlv(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    out.push(tag);
+    match value.len() {
+        len @ 0..=0x7f => out.push(len as u8),
+        len => {
+            let bytes = (len as u32).to_be_bytes();
+            let skip = bytes.iter().take_while(|&&b| b == 0).count();
+            out.push(0x80 | (4 - skip) as u8);
+            out.extend_from_slice(&bytes[skip..]);
+        }
+    }
+    out.extend_from_slice(value);
+}
+
+fn read_oids(mut input: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut oids = Vec::new();
+    while !input.is_empty() {
+        oids.push(read_tlv(&mut input, OBJECT_IDENTIFIER)?.to_vec());
+    }
+    Some(oids)
+}
+
+/// Like `read_tlv`, but returns `Some(None)` if `input` does not start with `tag`.
+fn read_optional<'a>(input: &mut &'a [u8], tag: u8) -> Option<Option<&'a [u8]>> {
+    match input.first() {
+        Some(&actual) if actual == tag => read_tlv(input, tag).map(Some),
+        _ => Some(None),
+    }
+}
+
+/// Read a DER tag-length-value triple with the given `tag` from the front of `input`,
+/// returning the value and advancing `input` past it.
+fn read_tlv<'a>(input: &mut &'a [u8], tag: u8) -> Option<&'a [u8]> {
+    let (&actual, rest) = input.split_first()?;
+    if actual != tag {
+        return None;
+    }
+
+    let (&first, mut rest) = rest.split_first()?;
+    let len = match first {
+        len @ 0..=0x7f => usize::from(len),
+        0x81..=0x84 => {
+            let count = usize::from(first & 0x7f);
+            if rest.len() < count {
+                return None;
+            }
+            let (bytes, after) = rest.split_at(count);
+            rest = after;
+            bytes
+                .iter()
+                .fold(0usize, |len, &b| (len << 8) | usize::from(b))
+        }
+        _ => return None,
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+
+    let (value, rest) = rest.split_at(len);
+    *input = rest;
+    Some(value)
+}
+
+const OCTET_STRING: u8 = 0x04;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+const SEQUENCE: u8 = 0x30;
+const CONTEXT_CONSTRUCTED_0: u8 = 0xa0;
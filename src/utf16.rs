@@ -0,0 +1,111 @@
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
+use std::io;
+use std::vec::Vec;
+
+/// The state of transcoding UTF-16 input to UTF-8.
+#[derive(Debug)]
+pub(crate) struct Utf16 {
+    big_endian: bool,
+    /// Input not yet transcoded: an odd byte, or a high surrogate awaiting its pair.
+    partial: Vec<u8>,
+    /// Transcoded output.
+    out: Vec<u8>,
+    /// The length of `out` already consumed.
+    used: usize,
+}
+
+impl Utf16 {
+    /// Detect UTF-16 from the byte order mark at the start of `rd`, without consuming it.
+    ///
+    /// The byte order mark is transcoded along with the rest of the input.
+    pub(crate) fn detect<R: io::BufRead + ?Sized>(rd: &mut R) -> io::Result<Option<Self>> {
+        let big_endian = match rd.fill_buf()? {
+            [0xfe, 0xff, ..] => true,
+            [0xff, 0xfe, ..] => false,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            big_endian,
+            partial: Vec::new(),
+            out: Vec::new(),
+            used: 0,
+        }))
+    }
+
+    /// Transcode the next chunk of `rd`, returning false at its end.
+    fn transcode<R: io::BufRead + ?Sized>(&mut self, rd: &mut R) -> io::Result<bool> {
+        let available = rd.fill_buf()?;
+        let eof = available.is_empty();
+        let mut bytes = core::mem::take(&mut self.partial);
+        bytes.extend_from_slice(available);
+        let len = available.len();
+        rd.consume(len);
+
+        // hold back anything that may be completed by the next chunk
+        let mut keep = bytes.len() % 2;
+        let end = bytes.len() - keep;
+        if !eof
+            && end >= 2
+            && (0xd800..0xdc00).contains(&unit(&bytes[end - 2..end], self.big_endian))
+        {
+            keep += 2;
+        }
+        let (whole, rest) = bytes.split_at(bytes.len() - keep);
+        self.partial = rest.to_vec();
+
+        let big_endian = self.big_endian;
+        let units = whole.chunks_exact(2).map(|bytes| unit(bytes, big_endian));
+
+        self.out.clear();
+        self.used = 0;
+        let mut char_buf = [0u8; 4];
+        for ch in decode_utf16(units) {
+            let ch = ch.unwrap_or(REPLACEMENT_CHARACTER);
+            self.out
+                .extend_from_slice(ch.encode_utf8(&mut char_buf).as_bytes());
+        }
+
+        Ok(!eof)
+    }
+}
+
+/// The UTF-16 code unit encoded in the two `bytes`.
+fn unit(bytes: &[u8], big_endian: bool) -> u16 {
+    match big_endian {
+        true => u16::from_be_bytes([bytes[0], bytes[1]]),
+        false => u16::from_le_bytes([bytes[0], bytes[1]]),
+    }
+}
+
+/// A reader of UTF-8 transcoded from the UTF-16 input in `rd`.
+pub(crate) struct Utf16Reader<'a, R: ?Sized> {
+    pub(crate) rd: &'a mut R,
+    pub(crate) utf16: &'a mut Utf16,
+}
+
+impl<R: io::BufRead + ?Sized> io::Read for Utf16Reader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = io::BufRead::fill_buf(self)?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        io::BufRead::consume(self, len);
+        Ok(len)
+    }
+}
+
+impl<R: io::BufRead + ?Sized> io::BufRead for Utf16Reader<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.utf16.used == self.utf16.out.len() {
+            if !self.utf16.transcode(self.rd)? {
+                break;
+            }
+        }
+
+        Ok(&self.utf16.out[self.utf16.used..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.utf16.used += amt;
+    }
+}
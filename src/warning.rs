@@ -0,0 +1,51 @@
+use alloc::string::String;
+
+/// An anomaly in PEM input that does not prevent it being read.
+///
+/// These are collected when [`ParserOptions::collect_warnings()`] is set.  Line numbers
+/// count from one.
+///
+/// [`ParserOptions::collect_warnings()`]: crate::ParserOptions::collect_warnings()
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// A base64 line, other than the last in its section, is not 64 characters long; or
+    /// the last is longer than that.
+    LineLength {
+        /// The line number.
+        line: usize,
+        /// The length of the line, excluding its line ending.
+        length: usize,
+    },
+
+    /// A line ends in spaces or tabs.
+    TrailingWhitespace {
+        /// The line number.
+        line: usize,
+    },
+
+    /// A section's label is not one this crate recognises.
+    UnknownLabel {
+        /// The line number of the section's BEGIN line.
+        line: usize,
+        /// The label.
+        label: String,
+    },
+
+    /// A section's label contains lower-case letters.
+    ///
+    /// Such sections are recognised only with
+    /// [`ParserOptions::ignore_label_case()`][crate::ParserOptions::ignore_label_case()].
+    LowercaseLabel {
+        /// The line number of the section's BEGIN line.
+        line: usize,
+        /// The label.
+        label: String,
+    },
+
+    /// A certificate is identical to one earlier in the input.
+    DuplicateCertificate {
+        /// The line number of the section's BEGIN line.
+        line: usize,
+    },
+}
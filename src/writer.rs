@@ -0,0 +1,774 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use pki_types::{
+    CertificateDer, CertificateRevocationListDer, CertificateSigningRequestDer, PrivateKeyDer,
+    PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer, SubjectPublicKeyInfoDer,
+};
+
+use crate::base64;
+use crate::pemfile::{crc24, Encapsulation, Item, RFC4716_BEGIN, RFC4716_END};
+use crate::trust;
+
+/// Encode `item` as PEM, and write it to `wr`.
+///
+/// The output conforms to RFC 7468: the contents are base64-encoded, wrapped at 64 columns,
+/// and each line (including the last) is terminated by a single `\n`.  Items that were read
+/// from other encapsulations (such as RFC 4716 SSH public keys or OpenPGP armor) are written
+/// back in those encapsulations.
+///
+/// Use [`WriteOptions::write_one()`] to choose a different line width or line ending.
+#[cfg(feature = "std")]
+pub fn write_one(wr: &mut dyn io::Write, item: &Item) -> Result<(), io::Error> {
+    WriteOptions::default().write_one(wr, item)
+}
+
+/// Encode each of `items` as PEM, and write them in order to `wr`.
+///
+/// See [`write_one()`] for details of the output.
+#[cfg(feature = "std")]
+pub fn write_all<'a>(
+    wr: &mut dyn io::Write,
+    items: impl IntoIterator<Item = &'a Item>,
+) -> Result<(), io::Error> {
+    WriteOptions::default().write_all(wr, items)
+}
+
+/// Encode `item` as PEM, and write it to the [`fmt::Write`] `wr`.
+///
+/// The output is as described for [`write_one()`].  Unlike that function, this is available
+/// without the "std" feature, for example to write into a fixed-size buffer.
+pub fn write_one_fmt(wr: &mut dyn fmt::Write, item: &Item) -> fmt::Result {
+    WriteOptions::default().write_one_fmt(wr, item)
+}
+
+/// Encode `item` as PEM into the start of `buf`, returning the number of bytes written.
+///
+/// The output is as described for [`write_one()`].  Returns an error if `buf` is too short
+/// to hold it.  This is available without the "std" feature.
+pub fn write_one_to_slice(buf: &mut [u8], item: &Item) -> Result<usize, fmt::Error> {
+    WriteOptions::default().write_one_to_slice(buf, item)
+}
+
+/// Encode `der` as a PEM section labelled `label`, and write it to `wr`.
+///
+/// This allows arbitrary data to be stored in PEM files under an application's own label;
+/// such sections are read back as [`Item::Unknown`] with
+/// [`ParserOptions::yield_unknown()`][crate::ParserOptions::yield_unknown()].  The output
+/// is otherwise as described for [`write_one()`].
+///
+/// `label` must be a valid RFC 7468 label: printable ASCII, not starting or ending with a
+/// space or hyphen, and with no two of those in a row.  Otherwise an error of kind
+/// [`io::ErrorKind::InvalidInput`] is returned, and nothing is written.
+#[cfg(feature = "std")]
+pub fn wrap_der(label: &str, der: &[u8], wr: &mut dyn io::Write) -> Result<(), io::Error> {
+    WriteOptions::default().wrap_der(label, der, wr)
+}
+
+/// Read every section from `rd`, and write it back to `wr` in canonical form.
+///
+/// Each section is written as described for [`write_one()`], with its canonical label
+/// (so, for example, "X509 CERTIFICATE" becomes "CERTIFICATE"); any text between or around
+/// sections is dropped.  The output is therefore the same for any two inputs containing the
+/// same items in the same order.  Any RFC 1421 headers, such as the `Proc-Type` and
+/// `DEK-Info` of a legacy encrypted key, are kept.
+#[cfg(feature = "std")]
+pub fn normalize(rd: &mut dyn io::BufRead, wr: &mut dyn io::Write) -> Result<(), io::Error> {
+    let options = WriteOptions::default();
+    let mut reader = crate::PemReader::new(rd);
+    while let Some(section) = reader.read_section()? {
+        // items such as OpenPGP armor carry their own headers, so are written with them
+        let headers = match Parts::new(&section.item).headers.is_empty() {
+            true => &section.headers[..],
+            false => &[],
+        };
+        options.write_with_headers(wr, &section.item, headers)?;
+    }
+
+    Ok(())
+}
+
+/// Options controlling how items are encoded as PEM.
+///
+/// The defaults produce the output described for [`write_one()`].
+///
+/// ```
+/// use rustls_pemfile::{LineEnding, WriteOptions};
+///
+/// // base64 as MIME would wrap it, for a Windows-oriented consumer
+/// let options = WriteOptions::new()
+///     .line_width(76)
+///     .line_ending(LineEnding::CrLf);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    line_width: usize,
+    line_ending: LineEnding,
+}
+
+impl WriteOptions {
+    /// Make a new `WriteOptions` with the default settings.
+    pub fn new() -> Self {
+        Self {
+            line_width: 64,
+            line_ending: LineEnding::Lf,
+        }
+    }
+
+    /// Set the number of base64 characters written on each line of the contents.
+    ///
+    /// The default is 64, as required by RFC 7468.  Values less than 4 are treated as 4.
+    pub fn line_width(mut self, width: usize) -> Self {
+        self.line_width = width.max(4);
+        self
+    }
+
+    /// Set the line terminator.
+    ///
+    /// The default is [`LineEnding::Lf`].
+    pub fn line_ending(mut self, ending: LineEnding) -> Self {
+        self.line_ending = ending;
+        self
+    }
+
+    /// Encode `item` as PEM using these options, and write it to `wr`.
+    #[cfg(feature = "std")]
+    pub fn write_one(&self, wr: &mut dyn io::Write, item: &Item) -> Result<(), io::Error> {
+        wr.write_all(self.to_pem_string(item).as_bytes())
+    }
+
+    /// Encode each of `items` as PEM using these options, and write them in order to `wr`.
+    #[cfg(feature = "std")]
+    pub fn write_all<'a>(
+        &self,
+        wr: &mut dyn io::Write,
+        items: impl IntoIterator<Item = &'a Item>,
+    ) -> Result<(), io::Error> {
+        for item in items {
+            self.write_one(wr, item)?;
+        }
+
+        Ok(())
+    }
+
+    /// Encode `item` as PEM using these options, and write it to the [`fmt::Write`] `wr`.
+    pub fn write_one_fmt(&self, wr: &mut dyn fmt::Write, item: &Item) -> fmt::Result {
+        wr.write_str(&self.to_pem_string(item))
+    }
+
+    /// Encode `item` as PEM using these options into the start of `buf`, returning the number
+    /// of bytes written.
+    ///
+    /// Returns an error if `buf` is too short.
+    pub fn write_one_to_slice(&self, buf: &mut [u8], item: &Item) -> Result<usize, fmt::Error> {
+        let pem = self.to_pem_string(item);
+        let out = buf.get_mut(..pem.len()).ok_or(fmt::Error)?;
+        out.copy_from_slice(pem.as_bytes());
+        Ok(pem.len())
+    }
+
+    /// Encode `item` as PEM using these options, with additional `headers`, and write it to `wr`.
+    ///
+    /// See [`WriteOptions::to_pem_string_with_headers()`] for how the headers are written.
+    #[cfg(feature = "std")]
+    pub fn write_with_headers(
+        &self,
+        wr: &mut dyn io::Write,
+        item: &Item,
+        headers: &[(String, String)],
+    ) -> Result<(), io::Error> {
+        wr.write_all(self.to_pem_string_with_headers(item, headers)?.as_bytes())
+    }
+
+    /// Encode `der` as a PEM section labelled `label` using these options, and write it to `wr`.
+    ///
+    /// See [`wrap_der()`] for the requirements on `label`.
+    #[cfg(feature = "std")]
+    pub fn wrap_der(
+        &self,
+        label: &str,
+        der: &[u8],
+        wr: &mut dyn io::Write,
+    ) -> Result<(), io::Error> {
+        check_label(label)?;
+        wr.write_all(self.encode_der(label, der).as_bytes())
+    }
+
+    /// Start a section labelled `label` in `wr`, whose contents are then written to the
+    /// returned [`SectionWriter`].
+    ///
+    /// See [`wrap_der()`] for the requirements on `label`.
+    #[cfg(feature = "std")]
+    pub fn section_writer<'a>(
+        &self,
+        wr: &'a mut dyn io::Write,
+        label: &str,
+    ) -> Result<SectionWriter<'a>, io::Error> {
+        check_label(label)?;
+        let mut buf = String::new();
+        push_boundary(&mut buf, "BEGIN", label, self.line_ending.as_str());
+        wr.write_all(buf.as_bytes())?;
+
+        Ok(SectionWriter {
+            wr,
+            label: String::from(label),
+            line_ending: self.line_ending,
+            encoder: base64::Encoder::new().wrap(self.line_width, self.line_ending),
+            buf,
+        })
+    }
+
+    /// Encode `item` as PEM using these options, returning the text.
+    pub fn to_pem_string(&self, item: &Item) -> String {
+        self.encode_item(item, &[])
+    }
+
+    /// Encode `key` as PEM using these options, with the label for its encoding, returning
+    /// the text.
+    ///
+    /// [`PrivateKeyDer`] is non-exhaustive, so this returns `None` for a key of an encoding
+    /// that this crate does not know the label of.
+    pub fn private_key_to_pem(&self, key: &PrivateKeyDer<'_>) -> Option<String> {
+        match key {
+            PrivateKeyDer::Pkcs1(key) => Some(key.to_pem_with(self)),
+            PrivateKeyDer::Sec1(key) => Some(key.to_pem_with(self)),
+            PrivateKeyDer::Pkcs8(key) => Some(key.to_pem_with(self)),
+            _ => None,
+        }
+    }
+
+    /// Encode `der` as a PEM section labelled `label`, using these options.
+    #[cfg_attr(not(any(feature = "std", feature = "crypto")), allow(dead_code))]
+    pub(crate) fn encode_der(&self, label: &str, der: &[u8]) -> String {
+        let mut out = String::new();
+        encode_parts(Parts::der(label, der), self, &mut out);
+        out
+    }
+
+    /// Encode `item` as PEM using these options, with additional `headers`, returning the text.
+    ///
+    /// The headers (such as `Proc-Type` or `DEK-Info`) are written as `Name: value` lines after
+    /// the BEGIN line, and are separated from the base64 body by a blank line, as specified in
+    /// RFC 1421.  For items that already carry headers, such as OpenPGP armor, `headers` are
+    /// written after the item's own.
+    ///
+    /// Header names must be non-empty and must not contain `:`, and neither names nor values
+    /// may contain line breaks.  Otherwise an error of kind [`io::ErrorKind::InvalidInput`]
+    /// is returned.
+    #[cfg(feature = "std")]
+    pub fn to_pem_string_with_headers(
+        &self,
+        item: &Item,
+        headers: &[(String, String)],
+    ) -> Result<String, io::Error> {
+        headers
+            .iter()
+            .try_for_each(|(name, value)| check_header(name, value))?;
+        Ok(self.encode_item(item, headers))
+    }
+
+    /// Encode `item` as PEM using these options, with additional `headers`.
+    fn encode_item(&self, item: &Item, headers: &[(String, String)]) -> String {
+        let mut parts = Parts::new(item);
+        if !headers.is_empty() {
+            parts.headers.to_mut().extend_from_slice(headers);
+        }
+
+        let mut out = String::new();
+        encode_parts(parts, self, &mut out);
+        out
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The line terminator used when writing PEM.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`, as used on Unix-like systems.
+    Lf,
+    /// `\r\n`, as used on Windows and in MIME.
+    CrLf,
+}
+
+impl LineEnding {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+impl Item {
+    /// Encode this item as PEM, returning the text.
+    ///
+    /// This produces the same output as [`write_one()`]; note that for private keys this
+    /// includes the key material.
+    pub fn to_pem_string(&self) -> String {
+        WriteOptions::default().to_pem_string(self)
+    }
+}
+
+/// Formats the item as PEM, as for [`Item::to_pem_string()`].
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_pem_string())
+    }
+}
+
+fn encode_parts(parts: Parts<'_>, options: &WriteOptions, out: &mut String) {
+    let Parts {
+        label,
+        contents,
+        headers,
+        encapsulation,
+    } = parts;
+    let newline = options.line_ending.as_str();
+
+    match encapsulation {
+        Encapsulation::Rfc7468 | Encapsulation::OpenPgp => {
+            push_boundary(out, "BEGIN", &label, newline);
+        }
+        Encapsulation::Rfc4716 => {
+            push_ascii(out, RFC4716_BEGIN);
+            out.push_str(newline);
+        }
+    }
+
+    for (name, value) in headers.iter() {
+        match encapsulation {
+            Encapsulation::Rfc4716 => push_folded_header(out, name, value, newline),
+            Encapsulation::Rfc7468 | Encapsulation::OpenPgp => {
+                out.push_str(name);
+                out.push_str(": ");
+                out.push_str(value);
+                out.push_str(newline);
+            }
+        }
+    }
+
+    // OpenPGP armor always has a blank line before the body, even without headers;
+    // RFC 1421 headers are likewise separated from the body by one
+    match encapsulation {
+        Encapsulation::OpenPgp => out.push_str(newline),
+        Encapsulation::Rfc7468 if !headers.is_empty() => out.push_str(newline),
+        _ => {}
+    }
+
+    let mut encoder = base64::Encoder::new().wrap(options.line_width, options.line_ending);
+    encoder.update(&contents, out);
+    encoder.finish(out);
+
+    match encapsulation {
+        Encapsulation::Rfc7468 => {}
+        Encapsulation::Rfc4716 => {
+            push_ascii(out, RFC4716_END);
+            out.push_str(newline);
+            return;
+        }
+        Encapsulation::OpenPgp => {
+            let crc = crc24(&contents).to_be_bytes();
+            out.push('=');
+            base64::encode(&crc[1..], out);
+            out.push_str(newline);
+        }
+    }
+
+    push_boundary(out, "END", &label, newline);
+}
+
+/// A writer that encodes everything written to it as the contents of one PEM section.
+///
+/// This allows very large items, such as CRLs of hundreds of megabytes, to be written
+/// without holding the whole DER encoding (or its base64) in memory.  Call
+/// [`SectionWriter::finish()`] once all the contents are written, to complete the section.
+///
+/// ```
+/// use std::io;
+/// use rustls_pemfile::SectionWriter;
+///
+/// # let mut crl_file = &b"\x30\x00"[..];
+/// let mut out = Vec::new();
+/// let mut writer = SectionWriter::new(&mut out, "X509 CRL").unwrap();
+/// io::copy(&mut crl_file, &mut writer).unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(out, b"-----BEGIN X509 CRL-----\nMAA=\n-----END X509 CRL-----\n");
+/// ```
+#[cfg(feature = "std")]
+pub struct SectionWriter<'a> {
+    wr: &'a mut dyn io::Write,
+    label: String,
+    line_ending: LineEnding,
+    encoder: base64::Encoder,
+    buf: String,
+}
+
+#[cfg(feature = "std")]
+impl<'a> SectionWriter<'a> {
+    /// Start a section labelled `label` in `wr`, using the default [`WriteOptions`].
+    ///
+    /// This writes the BEGIN line immediately.
+    pub fn new(wr: &'a mut dyn io::Write, label: &str) -> Result<Self, io::Error> {
+        WriteOptions::default().section_writer(wr, label)
+    }
+
+    /// Encode the remaining contents, and write the END line.
+    pub fn finish(mut self) -> Result<(), io::Error> {
+        self.encoder.finish(&mut self.buf);
+        push_boundary(&mut self.buf, "END", &self.label, self.line_ending.as_str());
+        self.wr.write_all(self.buf.as_bytes())
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for SectionWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.buf.clear();
+        self.encoder.update(buf, &mut self.buf);
+        self.wr.write_all(self.buf.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.wr.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for SectionWriter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SectionWriter")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Check `label` matches the `label` production of RFC 7468.
+#[cfg(feature = "std")]
+fn check_label(label: &str) -> Result<(), io::Error> {
+    let bytes = label.as_bytes();
+    let separator = |b: &u8| matches!(b, b' ' | b'-');
+    let valid = bytes.iter().all(|&b| b == b' ' || b.is_ascii_graphic())
+        && !matches!(bytes.first(), Some(b' ' | b'-'))
+        && !matches!(bytes.last(), Some(b' ' | b'-'))
+        && !bytes
+            .windows(2)
+            .any(|pair| separator(&pair[0]) && separator(&pair[1]));
+
+    match valid {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            std::format!("invalid PEM label {:?}", label),
+        )),
+    }
+}
+
+/// Check that a header named `name` with `value` can be written as one `Name: value` line,
+/// which reads back as the same header.
+///
+/// Readers trim whitespace around names and values, and take a line ending in a backslash
+/// to continue on the next.
+#[cfg(feature = "std")]
+fn check_header(name: &str, value: &str) -> Result<(), io::Error> {
+    let line_break = |c: char| matches!(c, '\r' | '\n');
+    let padded = |s: &str| s.starts_with(char::is_whitespace) || s.ends_with(char::is_whitespace);
+    let valid = !name.is_empty()
+        && !name.contains(|c| line_break(c) || c == ':')
+        && !value.contains(line_break)
+        && !value.ends_with('\\')
+        && !padded(name)
+        && !padded(value);
+
+    match valid {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            std::format!("invalid PEM header {:?}", name),
+        )),
+    }
+}
+
+/// Append a BEGIN or END line for `label`.
+fn push_boundary(out: &mut String, kind: &str, label: &str, newline: &str) {
+    out.push_str("-----");
+    out.push_str(kind);
+    out.push(' ');
+    out.push_str(label);
+    out.push_str("-----");
+    out.push_str(newline);
+}
+
+/// Encoding of [`pki_types`] DER types as PEM.
+///
+/// The PEM label is chosen according to the type, so (for example) a [`CertificateDer`]
+/// is written as a "CERTIFICATE" section.  A [`PrivateKeyDer`] may be of an encoding with
+/// no known label, so is written with [`WriteOptions::private_key_to_pem()`] instead.
+pub trait ToPem {
+    /// Encode this value as a PEM section using `options`, returning the text.
+    fn to_pem_with(&self, options: &WriteOptions) -> String;
+
+    /// Encode this value as a PEM section, returning the text.
+    ///
+    /// The output is as described for [`write_one()`].
+    fn to_pem(&self) -> String {
+        self.to_pem_with(&WriteOptions::default())
+    }
+
+    /// Encode this value as a PEM section, and write it to `wr`.
+    #[cfg(feature = "std")]
+    fn write_pem(&self, wr: &mut dyn io::Write) -> Result<(), io::Error> {
+        wr.write_all(self.to_pem().as_bytes())
+    }
+}
+
+macro_rules! to_pem {
+    ($ty:ident, $label:literal, $der:expr) => {
+        impl ToPem for $ty<'_> {
+            fn to_pem_with(&self, options: &WriteOptions) -> String {
+                let der: fn(&Self) -> &[u8] = $der;
+                let mut out = String::new();
+                encode_parts(Parts::der($label, der(self)), options, &mut out);
+                out
+            }
+        }
+    };
+}
+
+to_pem!(CertificateDer, "CERTIFICATE", |cert| cert);
+to_pem!(SubjectPublicKeyInfoDer, "PUBLIC KEY", |spki| spki);
+to_pem!(PrivatePkcs1KeyDer, "RSA PRIVATE KEY", |key| {
+    key.secret_pkcs1_der()
+});
+to_pem!(PrivatePkcs8KeyDer, "PRIVATE KEY", |key| {
+    key.secret_pkcs8_der()
+});
+to_pem!(PrivateSec1KeyDer, "EC PRIVATE KEY", |key| {
+    key.secret_sec1_der()
+});
+to_pem!(CertificateRevocationListDer, "X509 CRL", |crl| crl);
+to_pem!(CertificateSigningRequestDer, "CERTIFICATE REQUEST", |csr| {
+    csr
+});
+
+/// A builder for a combined PEM file holding a certificate chain and its private key.
+///
+/// The sections are written in the order expected by servers such as nginx and HAProxy:
+/// the end-entity certificate, then the intermediate certificates in the order given,
+/// then the private key.
+///
+/// ```
+/// use rustls_pemfile::BundleWriter;
+/// # use pki_types::{CertificateDer, PrivatePkcs8KeyDer};
+/// # let (cert, chain) = (CertificateDer::from(vec![0x30, 0x00]), vec![]);
+/// # let key = PrivatePkcs8KeyDer::from(vec![0x30, 0x00]);
+///
+/// let pem = BundleWriter::new()
+///     .end_entity(cert)
+///     .intermediates(chain)
+///     .key(key.into())
+///     .to_pem_string()
+///     .expect("the key's encoding is supported");
+/// ```
+#[derive(Debug, Default)]
+pub struct BundleWriter {
+    end_entity: Option<CertificateDer<'static>>,
+    intermediates: Vec<CertificateDer<'static>>,
+    key: Option<PrivateKeyDer<'static>>,
+    options: WriteOptions,
+}
+
+impl BundleWriter {
+    /// Make a new, empty `BundleWriter`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the end-entity certificate.
+    pub fn end_entity(mut self, cert: CertificateDer<'static>) -> Self {
+        self.end_entity = Some(cert);
+        self
+    }
+
+    /// Add intermediate certificates, in order from the one that issued the end-entity
+    /// certificate towards the root.
+    pub fn intermediates(
+        mut self,
+        certs: impl IntoIterator<Item = CertificateDer<'static>>,
+    ) -> Self {
+        self.intermediates.extend(certs);
+        self
+    }
+
+    /// Set the private key for the end-entity certificate.
+    pub fn key(mut self, key: PrivateKeyDer<'static>) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Set the [`WriteOptions`] used to encode each section.
+    pub fn options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Encode the bundle as PEM, and write it to `wr`.
+    ///
+    /// If the key cannot be encoded, as for [`BundleWriter::to_pem_string()`], an error of
+    /// kind [`io::ErrorKind::InvalidInput`] is returned, and nothing is written.
+    #[cfg(feature = "std")]
+    pub fn write(&self, wr: &mut dyn io::Write) -> Result<(), io::Error> {
+        let pem = self.to_pem_string().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported private key encoding",
+            )
+        })?;
+        wr.write_all(pem.as_bytes())
+    }
+
+    /// Encode the bundle as PEM, returning the text.
+    ///
+    /// Note that this includes the private key material, if a key was set.  Returns `None`
+    /// if the key is of an encoding that cannot be written: see
+    /// [`WriteOptions::private_key_to_pem()`].
+    pub fn to_pem_string(&self) -> Option<String> {
+        let mut out = String::new();
+        let certs = self.end_entity.iter().chain(&self.intermediates);
+        for cert in certs {
+            out.push_str(&cert.to_pem_with(&self.options));
+        }
+        if let Some(key) = &self.key {
+            out.push_str(&self.options.private_key_to_pem(key)?);
+        }
+        Some(out)
+    }
+}
+
+/// An item, broken down into what is needed to encode it.
+struct Parts<'a> {
+    label: Cow<'a, str>,
+    contents: Cow<'a, [u8]>,
+    headers: Cow<'a, [(String, String)]>,
+    encapsulation: Encapsulation,
+}
+
+impl<'a> Parts<'a> {
+    fn new(item: &'a Item) -> Self {
+        let (label, contents): (&'static str, Cow<'a, [u8]>) = match item {
+            Item::X509Certificate(cert) => ("CERTIFICATE", Cow::Borrowed(cert)),
+            Item::SubjectPublicKeyInfo(spki) => ("PUBLIC KEY", Cow::Borrowed(spki)),
+            Item::Pkcs1Key(key) => ("RSA PRIVATE KEY", Cow::Borrowed(key.secret_pkcs1_der())),
+            Item::Pkcs8Key(key) => ("PRIVATE KEY", Cow::Borrowed(key.secret_pkcs8_der())),
+            Item::Sec1Key(key) => ("EC PRIVATE KEY", Cow::Borrowed(key.secret_sec1_der())),
+            Item::Crl(crl) => ("X509 CRL", Cow::Borrowed(crl)),
+            Item::Csr(csr) => ("CERTIFICATE REQUEST", Cow::Borrowed(csr)),
+            Item::DsaKey(key) => ("DSA PRIVATE KEY", Cow::Borrowed(key)),
+            Item::Pkcs1PublicKey(key) => ("RSA PUBLIC KEY", Cow::Borrowed(key)),
+            Item::DhParameters(params) => ("DH PARAMETERS", Cow::Borrowed(params)),
+            Item::EcParameters(params) => ("EC PARAMETERS", Cow::Borrowed(params)),
+            Item::X942DhParameters(params) => ("X9.42 DH PARAMETERS", Cow::Borrowed(params)),
+            Item::DsaParameters(params) => ("DSA PARAMETERS", Cow::Borrowed(params)),
+            Item::AttributeCertificate(cert) => ("ATTRIBUTE CERTIFICATE", Cow::Borrowed(cert)),
+            Item::CertificatePair(pair) => ("CERTIFICATE PAIR", Cow::Borrowed(pair)),
+            Item::TrustAnchor(anchor) => ("TRUST ANCHOR", Cow::Borrowed(anchor)),
+            Item::OpenSshKey(key) => ("OPENSSH PRIVATE KEY", Cow::Borrowed(key)),
+            Item::OcspResponse(response) => ("OCSP RESPONSE", Cow::Borrowed(response)),
+            Item::TrustedCertificate { cert, trust } => {
+                ("TRUSTED CERTIFICATE", Cow::Owned(trust::join(cert, trust)))
+            }
+            Item::Pkcs7(p7) => ("PKCS7", Cow::Borrowed(p7)),
+            Item::Ssh2PublicKey { key, headers } => {
+                return Self::with_headers("SSH2 PUBLIC KEY", key, headers, Encapsulation::Rfc4716)
+            }
+            Item::PgpPublicKey { data, headers } => {
+                return Self::pgp("PGP PUBLIC KEY BLOCK", data, headers)
+            }
+            Item::PgpPrivateKey { data, headers } => {
+                return Self::pgp("PGP PRIVATE KEY BLOCK", data, headers)
+            }
+            Item::PgpSignature { data, headers } => {
+                return Self::pgp("PGP SIGNATURE", data, headers)
+            }
+            Item::PgpMessage { data, headers } => return Self::pgp("PGP MESSAGE", data, headers),
+            Item::LegacyEncrypted {
+                label,
+                data,
+                headers,
+            } => return Self::with_headers(label, data, headers, Encapsulation::Rfc7468),
+            Item::Unknown { label, der } => {
+                return Self {
+                    label: String::from_utf8_lossy(label),
+                    contents: Cow::Borrowed(der),
+                    headers: Cow::Borrowed(&[]),
+                    encapsulation: Encapsulation::Rfc7468,
+                }
+            }
+        };
+
+        Self {
+            label: Cow::Borrowed(label),
+            contents,
+            headers: Cow::Borrowed(&[]),
+            encapsulation: Encapsulation::Rfc7468,
+        }
+    }
+
+    fn der(label: &'a str, der: &'a [u8]) -> Self {
+        Self::with_headers(label, der, &[], Encapsulation::Rfc7468)
+    }
+
+    fn pgp(label: &'static str, data: &'a [u8], headers: &'a [(String, String)]) -> Self {
+        Self::with_headers(label, data, headers, Encapsulation::OpenPgp)
+    }
+
+    fn with_headers(
+        label: &'a str,
+        contents: &'a [u8],
+        headers: &'a [(String, String)],
+        encapsulation: Encapsulation,
+    ) -> Self {
+        Self {
+            label: Cow::Borrowed(label),
+            contents: Cow::Borrowed(contents),
+            headers: Cow::Borrowed(headers),
+            encapsulation,
+        }
+    }
+}
+
+/// Append an RFC 4716 header, folding it so no line exceeds 72 bytes.
+fn push_folded_header(out: &mut String, name: &str, value: &str, newline: &str) {
+    const MAX: usize = 72;
+
+    let mut line = String::with_capacity(MAX);
+    line.push_str(name);
+    line.push_str(": ");
+    for ch in value.chars() {
+        // leave room for the trailing backslash
+        if line.len() + ch.len_utf8() > MAX - 1 {
+            out.push_str(&line);
+            out.push('\\');
+            out.push_str(newline);
+            line.clear();
+        }
+        line.push(ch);
+    }
+
+    out.push_str(&line);
+    out.push_str(newline);
+}
+
+fn push_ascii(out: &mut String, bytes: &[u8]) {
+    out.extend(bytes.iter().copied().map(char::from));
+}